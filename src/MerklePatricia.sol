@@ -19,7 +19,9 @@ import {Option} from "./trie/Option.sol";
 import {NibbleSlice, NibbleSliceOps} from "./trie/NibbleSlice.sol";
 import {TrieDB} from "./trie/TrieDB.sol";
 import {SubstrateTrieDB} from "./trie/substrate/SubstrateTrieDB.sol";
+import {Blake2b} from "./trie/substrate/Blake2b.sol";
 import {EthereumTrieDB} from "./trie/ethereum/EthereumTrieDB.sol";
+import {RLPReader} from "./trie/ethereum/RLPReader.sol";
 
 /**
  * @title A Merkle Patricia library
@@ -28,6 +30,13 @@ import {EthereumTrieDB} from "./trie/ethereum/EthereumTrieDB.sol";
  * @dev refer to research for more info. https://research.polytope.technology/state-(machine)-proofs
  */
 library MerklePatricia {
+    using RLPReader for bytes;
+    using RLPReader for RLPReader.RLPItem;
+
+    // Root of the empty ethereum trie, the storage root of accounts without storage
+    bytes32 constant EMPTY_TRIE_ROOT =
+        0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421;
+
     // Outcome of a successfully verified merkle-patricia proof
     struct StorageValue {
         // the storage key
@@ -36,6 +45,15 @@ library MerklePatricia {
         bytes value;
     }
 
+    // An account in the ethereum state trie
+    struct EthereumAccount {
+        uint256 nonce;
+        uint256 balance;
+        // root of the account's storage trie
+        bytes32 storageRoot;
+        bytes32 codeHash;
+    }
+
     /**
      * @notice Verifies substrate specific merkle patricia proofs.
      * @param root hash of the merkle patricia trie
@@ -48,13 +66,42 @@ library MerklePatricia {
         bytes[] memory proof,
         bytes[] memory keys
     ) public pure returns (StorageValue[] memory) {
-        StorageValue[] memory values = new StorageValue[](keys.length);
         TrieNode[] memory nodes = new TrieNode[](proof.length);
 
         for (uint256 i = 0; i < proof.length; i++) {
             nodes[i] = TrieNode(keccak256(proof[i]), proof[i]);
         }
 
+        return lookupSubstrateKeys(nodes, root, keys);
+    }
+
+    /**
+     * @notice Verifies substrate specific merkle patricia proofs of tries
+     * hashed with blake2b-256, as the tries of most substrate chains are.
+     * @dev nodes are hashed with the BLAKE2 F precompile (EIP-152).
+     * @param root hash of the merkle patricia trie
+     * @param proof a list of proof nodes
+     * @param keys a list of keys to verify
+     * @return bytes[] a list of values corresponding to the supplied keys.
+     */
+    function VerifySubstrateProofBlake2(
+        bytes32 root,
+        bytes[] memory proof,
+        bytes[] memory keys
+    ) public view returns (StorageValue[] memory) {
+        return lookupSubstrateKeys(blake2Nodes(proof), root, keys);
+    }
+
+    /**
+     * @dev Looks up the keys in a substrate trie whose nodes are already hashed.
+     */
+    function lookupSubstrateKeys(
+        TrieNode[] memory nodes,
+        bytes32 root,
+        bytes[] memory keys
+    ) internal pure returns (StorageValue[] memory) {
+        StorageValue[] memory values = new StorageValue[](keys.length);
+
         for (uint256 i = 0; i < keys.length; i++) {
             values[i].key = keys[i];
             NibbleSlice memory keyNibbles = NibbleSlice(keys[i], 0);
@@ -142,17 +189,67 @@ library MerklePatricia {
         bytes[] memory keys,
         bytes memory childInfo
     ) public pure returns (StorageValue[] memory) {
+        TrieNode[] memory nodes = new TrieNode[](proof.length);
+
+        for (uint256 i = 0; i < proof.length; i++) {
+            nodes[i] = TrieNode(keccak256(proof[i]), proof[i]);
+        }
+
+        return lookupChildKeys(nodes, root, keys, childInfo);
+    }
+
+    /**
+     * @notice Verify child trie keys of tries hashed with blake2b-256
+     * @dev nodes are hashed with the BLAKE2 F precompile (EIP-152).
+     * @param root hash of the merkle root
+     * @param proof a list of proof nodes
+     * @param keys a list of keys to verify
+     * @param childInfo data that can be used to compute the root of the child trie
+     * @return bytes[], a list of values corresponding to the supplied keys.
+     */
+    function ReadChildProofCheckBlake2(
+        bytes32 root,
+        bytes[] memory proof,
+        bytes[] memory keys,
+        bytes memory childInfo
+    ) public view returns (StorageValue[] memory) {
+        return lookupChildKeys(blake2Nodes(proof), root, keys, childInfo);
+    }
+
+    /**
+     * @dev Looks up the keys in a child trie, whose root is read from the main
+     * trie, with the nodes of both tries already hashed.
+     */
+    function lookupChildKeys(
+        TrieNode[] memory nodes,
+        bytes32 root,
+        bytes[] memory keys,
+        bytes memory childInfo
+    ) internal pure returns (StorageValue[] memory) {
         // fetch the child trie root hash;
         bytes memory prefix = bytes(":child_storage:default:");
         bytes memory key = bytes.concat(prefix, childInfo);
         bytes[] memory _keys = new bytes[](1);
         _keys[0] = key;
-        StorageValue[] memory values = VerifySubstrateProof(root, proof, _keys);
+        StorageValue[] memory values = lookupSubstrateKeys(nodes, root, _keys);
 
         bytes32 childRoot = bytes32(values[0].value);
         require(childRoot != bytes32(0), "Invalid child trie proof");
 
-        return VerifySubstrateProof(childRoot, proof, keys);
+        return lookupSubstrateKeys(nodes, childRoot, keys);
+    }
+
+    /**
+     * @dev Hashes the proof nodes with blake2b-256.
+     */
+    function blake2Nodes(
+        bytes[] memory proof
+    ) internal view returns (TrieNode[] memory nodes) {
+        nodes = new TrieNode[](proof.length);
+
+        for (uint256 i = 0; i < proof.length; i++) {
+            nodes[i] = TrieNode(Blake2b.hash256(proof[i]), proof[i]);
+        }
     }
 
     /**
@@ -191,15 +288,6 @@ library MerklePatricia {
 
                 if (TrieDB.isLeaf(node)) {
                     Leaf memory leaf = EthereumTrieDB.decodeLeaf(node);
-                    // Let's retrieve the offset to be used
-                    uint256 offset = keyNibbles.offset % 2 == 0
-                        ? keyNibbles.offset / 2
-                        : keyNibbles.offset / 2 + 1;
-                    // Let's cut the key passed as input
-                    keyNibbles = NibbleSlice(
-                        NibbleSliceOps.bytesSlice(keyNibbles.data, offset),
-                        0
-                    );
                     if (NibbleSliceOps.eq(leaf.key, keyNibbles)) {
                         values[i].value = TrieDB.load(nodes, leaf.value);
                     }
@@ -256,4 +344,59 @@ library MerklePatricia {
 
         return values;
     }
+
+    /**
+     * @notice Verifies an account against the state root, then one of its storage slots against
+     * the account's storage root.
+     * @dev Accounts absent from the state trie and accounts without storage hold no slots, so the
+     * storage proof is not checked for them.
+     * @param stateRoot the state root of the block
+     * @param account address of the account
+     * @param accountProof proof of the account in the state trie
+     * @param slot the storage slot
+     * @param storageProof proof of the slot in the account's storage trie
+     * @return EthereumAccount the account, with every field zero if it does not exist.
+     * @return uint256 the value of the slot, zero for slots absent from the storage trie.
+     */
+    function VerifyEthereumStorageProof(
+        bytes32 stateRoot,
+        address account,
+        bytes[] memory accountProof,
+        bytes32 slot,
+        bytes[] memory storageProof
+    ) public pure returns (EthereumAccount memory, uint256) {
+        EthereumAccount memory decoded;
+        bytes[] memory keys = new bytes[](1);
+        keys[0] = abi.encodePacked(keccak256(abi.encodePacked(account)));
+        bytes memory encoded = VerifyEthereumProof(
+            stateRoot,
+            accountProof,
+            keys
+        )[0].value;
+        if (encoded.length == 0) {
+            return (decoded, 0);
+        }
+
+        RLPReader.RLPItem[] memory fields = encoded.toRlpItem().toList();
+        require(fields.length == 4, "Invalid account");
+        decoded.nonce = fields[0].toUint();
+        decoded.balance = fields[1].toUint();
+        decoded.storageRoot = bytes32(fields[2].toUintStrict());
+        decoded.codeHash = bytes32(fields[3].toUintStrict());
+        if (decoded.storageRoot == EMPTY_TRIE_ROOT) {
+            return (decoded, 0);
+        }
+
+        keys[0] = abi.encodePacked(keccak256(abi.encode(slot)));
+        bytes memory value = VerifyEthereumProof(
+            decoded.storageRoot,
+            storageProof,
+            keys
+        )[0].value;
+        if (value.length == 0) {
+            return (decoded, 0);
+        }
+
+        return (decoded, value.toRlpItem().toUint());
+    }
 }
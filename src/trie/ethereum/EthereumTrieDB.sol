@@ -75,8 +75,10 @@ library EthereumTrieDB {
             .toRlpItem()
             .toList();
         bytes memory data = decoded[1].toBytes();
-        // Remove the first byte, which is the prefix and not present in the user provided key
-        leaf.key = NibbleSlice(Bytes.substr(decoded[0].toBytes(), 1), 0);
+        bytes memory key = decoded[0].toBytes();
+        uint8 isOdd = uint8(key[0] >> 4) & 0x01;
+        // Skip the prefix nibble, and the padding nibble that follows it for even length keys
+        leaf.key = NibbleSlice(key, 2 - isOdd);
         leaf.value = NodeHandle(false, bytes32(0), true, data);
 
         return leaf;
@@ -91,19 +93,15 @@ library EthereumTrieDB {
             .data
             .toRlpItem()
             .toList();
-        bytes memory data = decoded[1].toBytes();
         uint8 isOdd = uint8(decoded[0].toBytes()[0] >> 4) & 0x01;
         // Remove the first byte, which is the prefix and not present in the user provided key
         extension.key = NibbleSlice(
             Bytes.substr(decoded[0].toBytes(), (isOdd + 1) % 2),
             isOdd
         );
-        extension.node = NodeHandle(
-            true,
-            Bytes.toBytes32(data),
-            false,
-            new bytes(0)
-        );
+        NodeHandleOption memory child = decodeNodeHandle(decoded[1]);
+        require(child.isSome, "Extension without child");
+        extension.node = child.value;
         return extension;
     }
 
@@ -120,21 +118,9 @@ library EthereumTrieDB {
         NodeHandleOption[16] memory childrens;
 
         for (uint256 i = 0; i < 16; i++) {
-            bytes memory dataAsBytes = decoded[i].toBytes();
-            if (dataAsBytes.length != 32) {
-                childrens[i] = NodeHandleOption(
-                    false,
-                    NodeHandle(false, bytes32(0), false, new bytes(0))
-                );
-            } else {
-                bytes32 data = Bytes.toBytes32(dataAsBytes);
-                childrens[i] = NodeHandleOption(
-                    true,
-                    NodeHandle(true, data, false, new bytes(0))
-                );
-            }
+            childrens[i] = decodeNodeHandle(decoded[i]);
         }
-        if (isEmpty(decoded[16].toBytes())) {
+        if (decoded[16].payloadLen() == 0) {
             branch.value = NodeHandleOption(
                 false,
                 NodeHandle(false, bytes32(0), false, new bytes(0))
@@ -150,7 +136,35 @@ library EthereumTrieDB {
         return branch;
     }
 
-    function isEmpty(bytes memory item) internal pure returns (bool) {
-        return item.length > 0 && (item[0] == 0xc0 || item[0] == 0x80);
+    /**
+     * @dev Decodes a reference to a child node: none for an empty string, the hash of the child
+     * for a 32 byte string, or the child itself when it is embedded as a list because its
+     * encoding is shorter than a hash.
+     */
+    function decodeNodeHandle(
+        RLPReader.RLPItem memory item
+    ) private pure returns (NodeHandleOption memory) {
+        if (item.isList()) {
+            return
+                NodeHandleOption(
+                    true,
+                    NodeHandle(false, bytes32(0), true, item.toRlpBytes())
+                );
+        }
+
+        bytes memory data = item.toBytes();
+        if (data.length == 0) {
+            return
+                NodeHandleOption(
+                    false,
+                    NodeHandle(false, bytes32(0), false, new bytes(0))
+                );
+        }
+        require(data.length == 32, "Invalid node handle");
+        return
+            NodeHandleOption(
+                true,
+                NodeHandle(true, Bytes.toBytes32(data), false, new bytes(0))
+            );
     }
 }
@@ -49,6 +49,8 @@ library RLPReader {
     }
 
     /*
+     * @dev Reverts if the length prefix of the item overruns it or stops short of its end, which
+     * costs one more read of the prefix per item.
      * @param item RLP encoded bytes
      */
     function toRlpItem(bytes memory item) internal pure returns (RLPItem memory) {
@@ -57,6 +59,7 @@ library RLPReader {
             memPtr := add(item, 0x20)
         }
 
+        require(item.length == 0 || _itemLength(memPtr) == item.length);
         return RLPItem(item.length, memPtr);
     }
 
@@ -266,6 +269,8 @@ library RLPReader {
             currPtr = currPtr + _itemLength(currPtr); // skip over an item
             count++;
         }
+        // the last item must not overrun the list
+        require(currPtr == endPtr);
 
         return count;
     }
@@ -0,0 +1,109 @@
+// Copyright (C) Polytope Labs Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+pragma solidity ^0.8.20;
+
+/**
+ * @title BLAKE2b-256 hashing
+ * @dev Most substrate chains hash their tries with BLAKE2b-256. Each 128 byte
+ * block of the input is compressed by the BLAKE2 F precompile of EIP-152.
+ */
+library Blake2b {
+    function hash256(bytes memory data) internal view returns (bytes32 digest) {
+        assembly {
+            // the precompile's input: rounds (4 bytes), state (64 bytes),
+            // message block (128 bytes), little endian offset counter
+            // (16 bytes) and final block flag (1 byte)
+            let input := mload(0x40)
+            mstore(0x40, add(input, 256))
+            mstore(input, shl(224, 12))
+            // the initialization vector, with the parameters of an unkeyed
+            // 32 byte digest mixed into its first word
+            mstore(
+                add(input, 4),
+                0x28c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5
+            )
+            mstore(
+                add(input, 36),
+                0xd182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b
+            )
+
+            let len := mload(data)
+            let src := add(data, 32)
+            let message := add(input, 68)
+            // the empty input is a single block of zeros
+            let blocks := add(div(len, 128), gt(mod(len, 128), 0))
+            if iszero(blocks) {
+                blocks := 1
+            }
+
+            for {
+                let i := 0
+            } lt(i, blocks) {
+                i := add(i, 1)
+            } {
+                let offset := mul(i, 128)
+                for {
+                    let j := 0
+                } lt(j, 128) {
+                    j := add(j, 32)
+                } {
+                    let word := 0
+                    let pos := add(offset, j)
+                    if lt(pos, len) {
+                        word := mload(add(src, pos))
+                        // zero the bytes past the end of the input
+                        let left := sub(len, pos)
+                        if lt(left, 32) {
+                            word := and(word, not(shr(mul(left, 8), not(0))))
+                        }
+                    }
+                    mstore(add(message, j), word)
+                }
+
+                // bytes compressed once this block is
+                let counter := add(offset, 128)
+                if gt(counter, len) {
+                    counter := len
+                }
+                mstore(add(input, 196), 0)
+                for {
+                    let k := 0
+                } lt(k, 8) {
+                    k := add(k, 1)
+                } {
+                    mstore8(add(input, add(196, k)), shr(mul(k, 8), counter))
+                }
+                mstore8(add(input, 212), eq(add(i, 1), blocks))
+
+                // the new state overwrites the old one, and chains without the
+                // precompile return nothing instead of failing
+                let success := staticcall(
+                    gas(),
+                    0x09,
+                    input,
+                    213,
+                    add(input, 4),
+                    64
+                )
+                if iszero(and(success, eq(returndatasize(), 64))) {
+                    revert(0, 0)
+                }
+            }
+
+            // the digest is the first 32 bytes of the state
+            digest := mload(add(input, 4))
+        }
+    }
+}
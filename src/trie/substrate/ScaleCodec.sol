@@ -47,6 +47,7 @@ library ScaleCodec {
             r <<= 6; // multiply by * 2^6
             r += b >> 2; // right shift to remove mode bits
             value = r;
+            require(value > 63, "non-canonical Compact<Uint>");
         } else if (mode == 2) {
             // [16384, 1073741823]
             uint8 b2 = Bytes.readByte(data); // read the next 3 bytes
@@ -59,11 +60,17 @@ library ScaleCodec {
 
             x3 >>= 2; // remove the last 2 mode bits
             value = uint256(x3);
+            require(value > 16383, "non-canonical Compact<Uint>");
         } else if (mode == 3) {
             // [1073741824, 4503599627370496]
             uint8 l = (b >> 2) + 4; // remove mode bits
             require(l <= 8, "unexpected prefix decoding Compact<Uint>");
-            return decodeUint256(Bytes.read(data, l));
+            value = decodeUint256(Bytes.read(data, l));
+            // the value must need every one of its l bytes, and more than the four-byte mode holds
+            require(
+                value >> (8 * (l - 1)) != 0 && value > 1073741823,
+                "non-canonical Compact<Uint>"
+            );
         } else {
             revert("Code should be unreachable");
         }
@@ -88,6 +95,7 @@ library ScaleCodec {
             r <<= 6; // multiply by * 2^6
             r += b >> 2; // right shift to remove mode bits
             value = r;
+            require(value > 63, "non-canonical Compact<Uint>");
         } else if (mode == 2) {
             // [16384, 1073741823]
             uint8 b2 = readByteAtIndex(data, 1); // read the next 3 bytes
@@ -100,6 +108,7 @@ library ScaleCodec {
 
             x3 >>= 2; // remove the last 2 mode bits
             value = uint256(x3);
+            require(value > 16383, "non-canonical Compact<Uint>");
         } else if (mode == 3) {
             // [1073741824, 4503599627370496]
             uint8 l = b >> 2; // remove mode bits
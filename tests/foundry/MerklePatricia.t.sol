@@ -6,6 +6,7 @@ import {MerklePatricia} from "../../src/MerklePatricia.sol";
 import {SubstrateTrieDB} from "../../src/trie/substrate/SubstrateTrieDB.sol";
 import {NodeKind, NibbledBranch, Leaf} from "../../src/trie/Node.sol";
 import {ScaleCodec} from "../../src/trie/substrate/ScaleCodec.sol";
+import {Blake2b} from "../../src/trie/substrate/Blake2b.sol";
 import {NibbleSlice, NibbleSliceOps} from "../../src/trie/NibbleSlice.sol";
 import {ByteSlice} from "../../src/trie/Bytes.sol";
 
@@ -89,6 +90,57 @@ contract MerklePatriciaTest is Test {
         assertEq(value, expectedOutput);
     }
 
+    // An absent key must not verify with the value of a leaf whose odd length
+    // partial key it differs from only in the leaf's first nibble: rlp(2)
+    // against the leaf of rlp(1) in a transaction trie of rlp(0) and rlp(1).
+    function testEthereumLeafWithOddPartialKey() public {
+        bytes[] memory proof = new bytes[](3);
+        proof[
+            0
+        ] = hex"f851a0b87abc27aad323b10dfb289092d88ec4cf6e0b5034b37bb4654ad9f37c59b15380808080808080a0db8c1e558d9f8070d397e20a81b95f388256577547acc046adaf9144113d69728080808080808080";
+        proof[
+            1
+        ] = hex"ea30a800000000000000000000000000000000000000000000000000000000000000000000000000000000";
+        proof[
+            2
+        ] = hex"ea31a801010101010101010101010101010101010101010101010101010101010101010101010101010101";
+        bytes32 root = hex"1c0bb17bad054ce9fdaae14c0d5f43fcfc6495884405bf3c9cdd2a13b166206d";
+
+        bytes[] memory keys = new bytes[](2);
+        keys[0] = hex"01";
+        keys[1] = hex"02";
+        MerklePatricia.StorageValue[] memory values = VerifyEthereum(
+            root,
+            proof,
+            keys
+        );
+        assertEq(
+            values[0].value,
+            hex"01010101010101010101010101010101010101010101010101010101010101010101010101010101"
+        );
+        assertEq(values[1].value, hex"");
+    }
+
+    // A branch value of the single byte 0x80, encoded as 0x8180, is a value
+    // and not the empty string of a branch without one.
+    function testEthereumBranchValueOf0x80() public {
+        bytes[] memory proof = new bytes[](3);
+        proof[
+            0
+        ] = hex"e4820012a0c964182ceff66e8acf9cf1fa2bbc761ef1e13822d572848fdf7f1f62cb2788a9";
+        proof[
+            1
+        ] = hex"f2808080a0811c4ca691216ff297b476af5021de9997323d016cc65251a5246b64b8d2376f8080808080808080808080808180";
+        proof[
+            2
+        ] = hex"ea34a802020202020202020202020202020202020202020202020202020202020202020202020202020202";
+        bytes32 root = hex"d2fc35d835632566af7dc7aba5894a8402d41f53472f75b937be80d5cb4eabcd";
+
+        bytes[] memory keys = new bytes[](1);
+        keys[0] = hex"12";
+        assertEq(VerifyEthereum(root, proof, keys)[0].value, hex"80");
+    }
+
     function VerifyKeys(
         bytes32 root,
         bytes[] memory proof,
@@ -97,6 +149,42 @@ contract MerklePatriciaTest is Test {
         return MerklePatricia.VerifySubstrateProof(root, proof, keys);
     }
 
+    function VerifyKeysBlake2(
+        bytes32 root,
+        bytes[] memory proof,
+        bytes[] memory keys
+    ) public view returns (MerklePatricia.StorageValue[] memory) {
+        return MerklePatricia.VerifySubstrateProofBlake2(root, proof, keys);
+    }
+
+    function ReadChild(
+        bytes32 root,
+        bytes[] memory proof,
+        bytes[] memory keys,
+        bytes memory childInfo
+    ) public pure returns (MerklePatricia.StorageValue[] memory) {
+        return MerklePatricia.ReadChildProofCheck(root, proof, keys, childInfo);
+    }
+
+    function ReadChildBlake2(
+        bytes32 root,
+        bytes[] memory proof,
+        bytes[] memory keys,
+        bytes memory childInfo
+    ) public view returns (MerklePatricia.StorageValue[] memory) {
+        return
+            MerklePatricia.ReadChildProofCheckBlake2(
+                root,
+                proof,
+                keys,
+                childInfo
+            );
+    }
+
+    function blake2b256(bytes memory data) public view returns (bytes32) {
+        return Blake2b.hash256(data);
+    }
+
     function VerifyEthereum(
         bytes32 root,
         bytes[] memory proof,
@@ -168,4 +256,11 @@ contract MerklePatriciaTest is Test {
     ) public pure returns (uint256) {
         return NibbleSliceOps.commonPrefix(self, other);
     }
+
+    function bytesSlice(
+        bytes memory data,
+        uint256 start
+    ) public pure returns (bytes memory) {
+        return NibbleSliceOps.bytesSlice(data, start);
+    }
 }
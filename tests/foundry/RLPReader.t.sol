@@ -0,0 +1,94 @@
+// Copyright (C) Polytope Labs Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+/*
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * 	http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {RLPReader} from "../../src/trie/ethereum/RLPReader.sol";
+
+contract RLPReaderTest is Test {
+    using RLPReader for bytes;
+    using RLPReader for RLPReader.RLPItem;
+
+    function isList(bytes memory encoded) public pure returns (bool) {
+        return encoded.toRlpItem().isList();
+    }
+
+    function payloadLen(bytes memory encoded) public pure returns (uint256) {
+        return encoded.toRlpItem().payloadLen();
+    }
+
+    function toBytes(
+        bytes memory encoded
+    ) public pure returns (bytes memory) {
+        return encoded.toRlpItem().toBytes();
+    }
+
+    function toUint(bytes memory encoded) public pure returns (uint256) {
+        return encoded.toRlpItem().toUint();
+    }
+
+    function toUintStrict(bytes memory encoded) public pure returns (uint256) {
+        return encoded.toRlpItem().toUintStrict();
+    }
+
+    // Returns where each list item starts within `encoded` and its length rather than a copy of
+    // each item.
+    function toList(
+        bytes memory encoded
+    ) public pure returns (uint256[] memory offsets, uint256[] memory lengths) {
+        RLPReader.RLPItem memory item = encoded.toRlpItem();
+        RLPReader.RLPItem[] memory items = item.toList();
+
+        offsets = new uint256[](items.length);
+        lengths = new uint256[](items.length);
+        for (uint256 i = 0; i < items.length; i++) {
+            offsets[i] = items[i].memPtr - item.memPtr;
+            lengths[i] = items[i].len;
+        }
+    }
+
+    function testReadsItemsThatFitTheirInput() public view {
+        assertEq(this.toBytes(hex"82aabb"), hex"aabb");
+
+        (uint256[] memory offsets, uint256[] memory lengths) = this.toList(
+            hex"c482aabb01"
+        );
+        assertEq(offsets.length, 2);
+        assertEq(offsets[0], 1);
+        assertEq(lengths[0], 3);
+        assertEq(offsets[1], 4);
+        assertEq(lengths[1], 1);
+    }
+
+    // A string claiming three bytes given only two.
+    function testRejectsTruncatedItem() public {
+        vm.expectRevert();
+        this.toBytes(hex"83aabb");
+    }
+
+    // A string of two bytes followed by a byte it doesn't claim.
+    function testRejectsTrailingBytes() public {
+        vm.expectRevert();
+        this.toBytes(hex"82aabbcc");
+    }
+
+    // A list of three bytes whose only item claims four.
+    function testRejectsItemOverrunningItsList() public {
+        vm.expectRevert();
+        this.toList(hex"c383aabb");
+    }
+}
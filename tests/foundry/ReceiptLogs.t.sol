@@ -0,0 +1,39 @@
+// Copyright (C) Polytope Labs Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+/*
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * 	http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+pragma solidity ^0.8.20;
+
+// Emits the events whose receipts the Rust receipt log proofs are tested with.
+contract EventEmitterFixture {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Message(uint256 indexed id, string text);
+
+    function transfer(address to, uint256 value) public {
+        emit Transfer(msg.sender, to, value);
+    }
+
+    // Emits a message on each side of the transfer, so that the transfer is
+    // not the first log of the receipt.
+    function transferWithMessages(
+        address to,
+        uint256 value,
+        string memory text
+    ) public {
+        emit Message(0, text);
+        emit Transfer(msg.sender, to, value);
+        emit Message(1, text);
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright (C) Polytope Labs Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+/*
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * 	http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {ScaleCodec} from "../../src/trie/substrate/ScaleCodec.sol";
+import {ByteSlice} from "../../src/trie/Bytes.sol";
+
+contract ScaleCodecTest is Test {
+    function decodeUint256(bytes memory data) public pure returns (uint256) {
+        return ScaleCodec.decodeUint256(data);
+    }
+
+    // Returns the decoded value and the number of bytes it was read from.
+    function decodeUintCompact(
+        bytes memory data
+    ) public pure returns (uint256 value, uint256 offset) {
+        ByteSlice memory slice = ByteSlice(data, 0);
+        value = ScaleCodec.decodeUintCompact(slice);
+        offset = slice.offset;
+    }
+
+    function decodeUintCompactWithMode(
+        bytes memory data
+    ) public pure returns (uint256 value, uint8 mode) {
+        return ScaleCodec.decodeUintCompact(data);
+    }
+
+    function encodeUintCompact(uint256 v) public pure returns (bytes memory) {
+        return ScaleCodec.encodeUintCompact(v);
+    }
+
+    function encodeBytes(
+        bytes memory input
+    ) public pure returns (bytes memory) {
+        return ScaleCodec.encodeBytes(input);
+    }
+
+    function reverse256(uint256 input) public pure returns (uint256) {
+        return ScaleCodec.reverse256(input);
+    }
+
+    function reverse128(uint128 input) public pure returns (uint128) {
+        return ScaleCodec.reverse128(input);
+    }
+
+    function reverse64(uint64 input) public pure returns (uint64) {
+        return ScaleCodec.reverse64(input);
+    }
+
+    function reverse32(uint32 input) public pure returns (uint32) {
+        return ScaleCodec.reverse32(input);
+    }
+
+    function reverse16(uint16 input) public pure returns (uint16) {
+        return ScaleCodec.reverse16(input);
+    }
+}
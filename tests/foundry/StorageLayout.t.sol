@@ -0,0 +1,89 @@
+// Copyright (C) Polytope Labs Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+/*
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * 	http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+pragma solidity ^0.8.20;
+
+// State variables of the kinds whose slots the Rust storage layout helpers compute. It does not
+// inherit Test, so that its own variables start at slot 0.
+contract StorageLayoutFixture {
+    struct Member {
+        uint128 balance;
+        uint64 nonce;
+        // does not fit in the rest of the first slot
+        address owner;
+        uint256 total;
+    }
+
+    uint256 public value; // slot 0
+    uint128 public low; // slot 1, offset 0
+    uint64 public mid; // slot 1, offset 16
+    bool public flag; // slot 1, offset 24
+    mapping(address => uint256) public balances; // slot 2
+    mapping(address => mapping(uint256 => bytes32)) public allowances; // slot 3
+    mapping(string => uint256) public names; // slot 4
+    uint256[] public numbers; // slot 5
+    uint64[] public packedNumbers; // slot 6
+    Member public member; // slots 7 to 9
+    Member[] public members; // slot 10
+    bytes public data; // slot 11
+    string public text; // slot 12
+
+    function setValue(uint256 _value) public {
+        value = _value;
+    }
+
+    function setPacked(uint128 _low, uint64 _mid, bool _flag) public {
+        low = _low;
+        mid = _mid;
+        flag = _flag;
+    }
+
+    function setBalance(address owner, uint256 balance) public {
+        balances[owner] = balance;
+    }
+
+    function setAllowance(address owner, uint256 id, bytes32 allowance) public {
+        allowances[owner][id] = allowance;
+    }
+
+    function setName(string memory name, uint256 id) public {
+        names[name] = id;
+    }
+
+    function pushNumber(uint256 number) public {
+        numbers.push(number);
+    }
+
+    function pushPackedNumber(uint64 number) public {
+        packedNumbers.push(number);
+    }
+
+    function setMember(Member memory _member) public {
+        member = _member;
+    }
+
+    function pushMember(Member memory _member) public {
+        members.push(_member);
+    }
+
+    function setData(bytes memory _data) public {
+        data = _data;
+    }
+
+    function setText(string memory _text) public {
+        text = _text;
+    }
+}
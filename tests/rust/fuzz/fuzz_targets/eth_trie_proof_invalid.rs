@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solidity_merkle_trees_fuzz::ethereum_trie::{
+    fuzz_that_verify_ethereum_rejects_invalid_proofs, EthTrieInput,
+};
+
+fuzz_target!(|input: EthTrieInput| {
+    fuzz_that_verify_ethereum_rejects_invalid_proofs(input);
+});
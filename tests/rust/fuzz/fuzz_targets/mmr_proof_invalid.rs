@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solidity_merkle_trees_fuzz::mmr::fuzz_that_mmr_rejects_invalid_proofs;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz_that_mmr_rejects_invalid_proofs(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solidity_merkle_trees_fuzz::multi_proof::fuzz_that_multi_proof_accepts_valid_proofs;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz_that_multi_proof_accepts_valid_proofs(data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solidity_merkle_trees_fuzz::node_codec::{fuzz_that_node_decoders_match_sp_trie, NodeInput};
+
+fuzz_target!(|input: NodeInput| {
+    fuzz_that_node_decoders_match_sp_trie(input);
+});
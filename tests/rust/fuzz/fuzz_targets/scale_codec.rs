@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solidity_merkle_trees_fuzz::scale_codec::fuzz_that_scale_codec_matches_parity_scale_codec;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz_that_scale_codec_matches_parity_scale_codec(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solidity_merkle_trees_fuzz::{fuzz_that_verify_rejects_invalid_proofs, TrieInput};
+use sp_core::KeccakHasher;
+use sp_trie::LayoutV1;
+
+fuzz_target!(|input: TrieInput| {
+    fuzz_that_verify_rejects_invalid_proofs::<LayoutV1<KeccakHasher>>(input);
+});
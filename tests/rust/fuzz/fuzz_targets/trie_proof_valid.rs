@@ -1,8 +1,10 @@
 #![no_main]
 
 use libfuzzer_sys::fuzz_target;
-use solidity_merkle_trees_fuzz::fuzz_that_verify_accepts_valid_proofs;
+use solidity_merkle_trees_fuzz::{fuzz_that_verify_accepts_valid_proofs, TrieInput};
+use sp_core::KeccakHasher;
+use sp_trie::LayoutV0;
 
-fuzz_target!(|data: &[u8]| {
-    fuzz_that_verify_accepts_valid_proofs(data);
+fuzz_target!(|input: TrieInput| {
+    fuzz_that_verify_accepts_valid_proofs::<LayoutV0<KeccakHasher>>(input);
 });
@@ -0,0 +1,97 @@
+use crate::TrieInput;
+use alloy_primitives::{keccak256, FixedBytes};
+use alloy_sol_types::sol;
+use arbitrary::Arbitrary;
+use solidity_merkle_trees_test::{
+    ethereum_trie::EthereumLayout,
+    evm_runner::{project_root, EvmRunner},
+    trie_utils::{generate_trie_proof, TrieProof},
+};
+use sp_core::H256;
+use trie_db::DBValue;
+
+sol! {
+    struct StorageValue {
+        bytes key;
+        bytes value;
+    }
+
+    function VerifyEthereum(bytes32 root, bytes[] proof, bytes[] keys) external pure returns (StorageValue[]);
+}
+
+/// The entries of an Ethereum trie and the keys to prove against it.
+#[derive(Debug, Arbitrary)]
+pub struct EthTrieInput {
+    trie: TrieInput,
+    /// Whether the trie is keyed by the keccak hash of each key, as the state and storage tries
+    /// are, rather than by the key itself, as the transaction and receipt tries are.
+    secure: bool,
+}
+
+/// Build the trie described by `input` and prove its keys.
+fn generate_proof(input: &EthTrieInput) -> Option<TrieProof<H256>> {
+    let (mut entries, mut keys) = input.trie.entries_and_keys();
+    if keys.is_empty() {
+        return None
+    }
+    if input.secure {
+        for (key, _) in &mut entries {
+            *key = keccak256(key.as_slice()).to_vec();
+        }
+        for key in &mut keys {
+            *key = keccak256(key.as_slice()).to_vec();
+        }
+    }
+
+    let proof = generate_trie_proof::<EthereumLayout>(&entries, &keys);
+    if proof.proof.is_empty() {
+        return None
+    }
+    Some(proof)
+}
+
+/// Verify each proven key on its own, returning the value the verifier found or why it reverted.
+fn verify(proof: &TrieProof<H256>) -> Vec<Result<Option<Vec<u8>>, String>> {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let addr = runner.deploy(&project, "MerklePatriciaTest");
+
+    proof
+        .items
+        .iter()
+        .map(|(key, _)| {
+            let call = VerifyEthereumCall {
+                root: FixedBytes(proof.root.into()),
+                proof: proof.proof.clone().into_iter().map(Into::into).collect(),
+                keys: vec![key.clone().into()],
+            };
+            let value = runner.call_sol(addr, call)?._0[0].value.to_vec();
+            Ok((!value.is_empty()).then_some(value))
+        })
+        .collect()
+}
+
+pub fn fuzz_that_verify_ethereum_rejects_invalid_proofs(input: EthTrieInput) {
+    let Some(mut proof) = generate_proof(&input) else { return };
+
+    // Make all items incorrect.
+    for (_, value) in &mut proof.items {
+        match value {
+            Some(value) if input.trie.extend_values => value.push(0),
+            Some(_) => *value = None,
+            None => *value = Some(DBValue::new()),
+        }
+    }
+
+    for (result, (key, value)) in verify(&proof).into_iter().zip(&proof.items) {
+        assert_ne!(result.as_ref(), Ok(value), "key={key:?}");
+    }
+}
+
+pub fn fuzz_that_verify_ethereum_accepts_valid_proofs(input: EthTrieInput) {
+    let Some(proof) = generate_proof(&input) else { return };
+
+    for (result, (key, value)) in verify(&proof).into_iter().zip(&proof.items) {
+        assert_eq!(result.as_ref(), Ok(value), "key={key:?}");
+    }
+}
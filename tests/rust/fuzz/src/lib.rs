@@ -1,14 +1,21 @@
 #![allow(dead_code, unused_imports)]
 
-use alloy_primitives::FixedBytes;
+pub mod ethereum_trie;
+pub mod mmr;
+pub mod multi_proof;
+pub mod node_codec;
+pub mod rlp;
+pub mod scale_codec;
+
+use alloy_primitives::{Address, FixedBytes};
 use alloy_sol_types::{sol, SolCall};
-use solidity_merkle_trees_test::evm_runner::{project_root, EvmRunner};
-use sp_core::KeccakHasher;
-use sp_trie::{LayoutV0, MemoryDB, StorageProof};
-use std::collections::HashSet;
-use trie_db::{
-    DBValue, Hasher, Recorder, Trie, TrieDBBuilder, TrieDBMutBuilder, TrieLayout, TrieMut,
+use arbitrary::Arbitrary;
+use solidity_merkle_trees_test::{
+    evm_runner::{project_root, EvmRunner},
+    trie_utils::{generate_trie_proof, TrieProof},
 };
+use std::collections::BTreeMap;
+use trie_db::{DBValue, TrieHash, TrieLayout};
 
 sol! {
     struct StorageValue {
@@ -19,125 +26,123 @@ sol! {
     function VerifyKeys(bytes32 root, bytes[] proof, bytes[] keys) external pure returns (StorageValue[]);
 }
 
-fn data_sorted_unique(input: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<(Vec<u8>, Vec<u8>)> {
-    let mut m = std::collections::BTreeMap::new();
-    for (k, v) in input.into_iter() {
-        let _ = m.insert(k, v);
+/// Call `contract`, requiring that it either returns or reverts cleanly.
+pub fn call_or_revert<C: SolCall>(
+    runner: &mut EvmRunner,
+    contract: Address,
+    call: C,
+) -> Option<C::Return> {
+    match runner.call_sol(contract, call) {
+        Ok(decoded) => Some(decoded),
+        Err(e) => {
+            assert!(e.starts_with("reverted"), "{} did not revert cleanly: {e}", C::SIGNATURE);
+            None
+        },
     }
-    m.into_iter().collect()
 }
 
-fn fuzz_to_data(input: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
-    let mut result = Vec::new();
-    let mut minkeylen = if let Some(v) = input.get(0) {
-        let mut v = *v & 31u8;
-        v = v + 1;
-        v
-    } else {
-        return result;
-    };
-    let mut maxkeylen = if let Some(v) = input.get(1) {
-        let mut v = *v & 31u8;
-        v = v + 1;
-        v
-    } else {
-        return result;
-    };
-
-    if maxkeylen < minkeylen {
-        let v = minkeylen;
-        minkeylen = maxkeylen;
-        maxkeylen = v;
-    }
-    let mut ix = 2;
-    loop {
-        let keylen = if let Some(v) = input.get(ix) {
-            let mut v = *v & 31u8;
-            v = v + 1;
-            v = std::cmp::max(minkeylen, v);
-            v = std::cmp::min(maxkeylen, v);
-            v as usize
-        } else {
-            break;
+/// The longest key built from fuzz input.
+const MAX_KEY_LEN: usize = 64;
+
+/// Values range up to this length so that they cross the 32 byte threshold at which nodes stop
+/// being inlined into their parent, and above which `LayoutV1` stores values hashed.
+const MAX_VALUE_LEN: usize = 96;
+
+/// A key made of a prefix of an earlier key followed by fresh bytes, so that keys share prefixes
+/// and the trie grows branches and extensions rather than a flat set of leaves.
+#[derive(Debug, Arbitrary)]
+pub struct KeySpec {
+    /// The earlier key to share a prefix with and the length of that prefix.
+    shared: Option<(u8, u8)>,
+    suffix: Vec<u8>,
+}
+
+impl KeySpec {
+    fn build(&self, keys: &[Vec<u8>]) -> Vec<u8> {
+        let mut key = match self.shared {
+            Some((index, len)) if !keys.is_empty() => {
+                let base = &keys[index as usize % keys.len()];
+                base[..len as usize % (base.len() + 1)].to_vec()
+            },
+            _ => Vec::new(),
         };
-        let key = if input.len() > ix + keylen { input[ix..ix + keylen].to_vec() } else { break };
-        ix += keylen;
-        let val = if input.len() > ix + 2 { input[ix..ix + 2].to_vec() } else { break };
-        result.push((key, val));
+        key.extend_from_slice(&self.suffix);
+        key.truncate(MAX_KEY_LEN);
+        key
     }
-    result
 }
 
-fn test_generate_proof<L: TrieLayout>(
-    entries: Vec<(Vec<u8>, Vec<u8>)>,
-    keys: Vec<Vec<u8>>,
-) -> (<L::Hash as Hasher>::Out, Vec<Vec<u8>>, Vec<(Vec<u8>, Option<DBValue>)>) {
-    let (db, root) = {
-        let mut db = <MemoryDB<L::Hash>>::default();
-        let mut root = Default::default();
-        {
-            let mut trie = TrieDBMutBuilder::<L>::new(&mut db, &mut root).build();
-            for (key, value) in &entries {
-                trie.insert(key, value).unwrap();
-            }
-        }
-        (db, root)
-    };
+/// A value of `len % MAX_VALUE_LEN + 1` bytes counting up from `first`. Values are never empty
+/// since inserting an empty value removes the key.
+#[derive(Debug, Arbitrary)]
+pub struct ValueSpec {
+    len: u8,
+    first: u8,
+}
 
-    let proof = {
-        let mut recorder = Recorder::<L>::new();
-        let trie_db = TrieDBBuilder::<L>::new(&db, &root).with_recorder(&mut recorder).build();
+impl ValueSpec {
+    fn build(&self) -> Vec<u8> {
+        (0..self.len as usize % MAX_VALUE_LEN + 1)
+            .map(|i| self.first.wrapping_add(i as u8))
+            .collect()
+    }
+}
 
-        for (key, expected) in &entries {
-            let value = trie_db.get(key).unwrap().unwrap();
-            assert_eq!(&value, expected);
-        }
+/// The entries of a trie and the keys to prove against it.
+#[derive(Debug, Arbitrary)]
+pub struct TrieInput {
+    entries: Vec<(KeySpec, ValueSpec)>,
+    /// Indices of the entries whose keys are proven.
+    proven: Vec<u16>,
+    /// Keys proven absent, built from prefixes of the entry keys.
+    absent: Vec<KeySpec>,
+    /// Whether invalid proofs claim a longer value for present keys rather than no value.
+    extend_values: bool,
+}
 
-        let proof = recorder.drain().into_iter().map(|f| f.data).collect::<HashSet<_>>();
-        {
-            let mdb = StorageProof::new(proof.clone()).into_memory_db::<L::Hash>();
-            let trie_db = TrieDBBuilder::<L>::new(&mdb, &root).build();
-            for (key, expected) in &entries {
-                let value = trie_db.get(key).unwrap().unwrap();
-                assert_eq!(&value, expected);
+impl TrieInput {
+    /// Returns the sorted, unique entries of the trie and the sorted, unique keys to prove.
+    fn entries_and_keys(&self) -> (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>) {
+        let mut entries = BTreeMap::new();
+        let mut entry_keys = Vec::new();
+        for (key, value) in &self.entries {
+            let key = key.build(&entry_keys);
+            if key.is_empty() {
+                continue
             }
+            entry_keys.push(key.clone());
+            entries.insert(key, value.build());
         }
 
-        proof.into_iter().collect::<Vec<_>>()
-    };
-
-    let trie = TrieDBBuilder::<L>::new(&db, &root).build();
-    let items = keys
-        .into_iter()
-        .map(|key| {
-            let value = trie.get(&key).unwrap();
-            (key, value)
-        })
-        .collect();
-
-    (root, proof, items)
-}
-
-pub fn fuzz_that_verify_rejects_invalid_proofs(input: &[u8]) {
-    if input.len() < 4 {
-        return;
+        let mut keys = Vec::new();
+        if !entry_keys.is_empty() {
+            keys.extend(
+                self.proven.iter().map(|&i| entry_keys[i as usize % entry_keys.len()].clone()),
+            );
+        }
+        keys.extend(
+            self.absent
+                .iter()
+                .map(|key| key.build(&entry_keys))
+                .filter(|key| !key.is_empty() && !entries.contains_key(key)),
+        );
+        keys.sort();
+        keys.dedup();
+
+        (entries.into_iter().collect(), keys)
     }
+}
 
-    let random_int = u32::from_le_bytes(input[0..4].try_into().expect("slice is 4 bytes")) as usize;
-
-    let mut data = fuzz_to_data(&input[4..]);
-    let mut keys = data[(data.len() / 3)..].iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
-    data.truncate(data.len() * 2 / 3);
-
-    let data = data_sorted_unique(data);
-    keys.sort();
-    keys.dedup();
-
+pub fn fuzz_that_verify_rejects_invalid_proofs<L: TrieLayout>(input: TrieInput)
+where
+    TrieHash<L>: Into<[u8; 32]>,
+{
+    let (entries, keys) = input.entries_and_keys();
     if keys.is_empty() {
         return;
     }
 
-    let (root, proof, mut items) = test_generate_proof::<LayoutV0<KeccakHasher>>(data, keys);
+    let TrieProof { root, proof, mut items } = generate_trie_proof::<L>(&entries, &keys);
 
     if proof.is_empty() {
         return;
@@ -146,7 +151,7 @@ pub fn fuzz_that_verify_rejects_invalid_proofs(input: &[u8]) {
     // Make all items incorrect.
     for i in 0..items.len() {
         match &mut items[i] {
-            (_, Some(value)) if random_int % 2 == 0 => value.push(0),
+            (_, Some(value)) if input.extend_values => value.push(0),
             (_, value) if value.is_some() => *value = None,
             (_, value) => *value = Some(DBValue::new()),
         }
@@ -171,16 +176,16 @@ pub fn fuzz_that_verify_rejects_invalid_proofs(input: &[u8]) {
     }
 }
 
-pub fn fuzz_that_verify_accepts_valid_proofs(input: &[u8]) {
-    let mut data = fuzz_to_data(input);
-    let mut keys = data[(data.len() / 3)..].iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
-    data.truncate(data.len() * 2 / 3);
-
-    let data = data_sorted_unique(data);
-    keys.sort();
-    keys.dedup();
+pub fn fuzz_that_verify_accepts_valid_proofs<L: TrieLayout>(input: TrieInput)
+where
+    TrieHash<L>: Into<[u8; 32]>,
+{
+    let (entries, keys) = input.entries_and_keys();
+    if keys.is_empty() {
+        return;
+    }
 
-    let (root, proof, items) = test_generate_proof::<LayoutV0<KeccakHasher>>(data, keys);
+    let TrieProof { root, proof, items } = generate_trie_proof::<L>(&entries, &keys);
 
     if proof.is_empty() {
         return;
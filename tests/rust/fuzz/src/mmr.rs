@@ -0,0 +1,87 @@
+use alloy_primitives::{Address, FixedBytes, U256};
+use alloy_sol_types::sol;
+use solidity_merkle_trees_test::{
+    evm_runner::{project_root, EvmRunner},
+    mmr_utils::{generate_mmr_proof, MmrProof},
+    mutations::{check_mutations, Mutation, MMR_WEAKNESSES},
+};
+
+sol! {
+    struct MmrLeaf {
+        uint256 index;
+        bytes32 hash;
+    }
+
+    function CalculateRoot(bytes32[] proof, MmrLeaf[] leaves, uint256 leafCount) external pure returns (bytes32);
+}
+
+/// The largest MMR built from fuzz input.
+const MAX_LEAVES: u32 = 1024;
+
+/// Decode a leaf count, the indices of the leaves to prove and a mutation seed from fuzz input.
+fn fuzz_to_mmr_input(input: &[u8]) -> Option<(u32, Vec<u32>, u64)> {
+    let (leaf_count, input) = input.split_first_chunk::<2>()?;
+    let (seed, input) = input.split_first_chunk::<8>()?;
+
+    let leaf_count = u16::from_le_bytes(*leaf_count) as u32 % MAX_LEAVES + 1;
+    let indices = input
+        .chunks_exact(2)
+        .map(|index| u16::from_le_bytes([index[0], index[1]]) as u32 % leaf_count)
+        .collect::<Vec<_>>();
+
+    if indices.is_empty() {
+        return None
+    }
+
+    Some((leaf_count, indices, u64::from_le_bytes(*seed)))
+}
+
+fn calculate_root(
+    runner: &mut EvmRunner,
+    contract: Address,
+    proof: &MmrProof,
+) -> Result<[u8; 32], String> {
+    let call = CalculateRootCall {
+        proof: proof.proof_items.iter().map(|item| FixedBytes(item.0)).collect(),
+        leaves: proof
+            .leaves
+            .iter()
+            .map(|leaf| MmrLeaf { index: U256::from(leaf.index), hash: FixedBytes(leaf.hash.0) })
+            .collect(),
+        leafCount: U256::from(proof.leaf_count),
+    };
+    Ok(runner.call_sol(contract, call)?._0.0)
+}
+
+fn setup() -> (EvmRunner, Address) {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let contract = runner.deploy(&project, "MerkleMountainRangeTest");
+    (runner, contract)
+}
+
+pub fn fuzz_that_mmr_accepts_valid_proofs(input: &[u8]) {
+    let Some((leaf_count, indices, _)) = fuzz_to_mmr_input(input) else { return };
+    let proof = generate_mmr_proof(leaf_count, &indices);
+
+    let (mut runner, contract) = setup();
+    let root = calculate_root(&mut runner, contract, &proof)
+        .expect("CalculateRoot should not revert for a valid proof");
+
+    assert_eq!(root, proof.root.0, "root mismatch for leaf_count={leaf_count}");
+}
+
+pub fn fuzz_that_mmr_rejects_invalid_proofs(input: &[u8]) {
+    let Some((leaf_count, indices, seed)) = fuzz_to_mmr_input(input) else { return };
+    let proof = generate_mmr_proof(leaf_count, &indices);
+    // the known weaknesses of the verifier are left to its ignored mutation tests
+    let mutations = Mutation::all_except(MMR_WEAKNESSES);
+    let mutation = mutations[(seed % mutations.len() as u64) as usize];
+
+    let (mut runner, contract) = setup();
+    let report = check_mutations(&proof, &[mutation], &[], seed, |mutated| {
+        calculate_root(&mut runner, contract, mutated).is_ok_and(|root| root == proof.root.0)
+    });
+
+    assert!(report.accepted.is_empty(), "{mutation:?} verified for leaf_count={leaf_count}");
+}
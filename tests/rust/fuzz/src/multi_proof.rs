@@ -0,0 +1,93 @@
+use alloy_primitives::{Address, FixedBytes, U256};
+use alloy_sol_types::sol;
+use solidity_merkle_trees_test::{
+    evm_runner::{project_root, EvmRunner},
+    multi_proof_utils::{generate_multi_proof, MultiProof},
+    mutations::{check_mutations, Mutation, MULTI_PROOF_WEAKNESSES},
+};
+
+sol! {
+    struct MpLeaf {
+        uint256 index;
+        bytes32 hash;
+    }
+
+    function CalculateRoot(bytes32[] proof, MpLeaf[] leaves, uint256 numLeaves) external view returns (bytes32);
+}
+
+/// The largest tree built from fuzz input.
+const MAX_LEAVES: usize = 2048;
+
+/// Decode an unbalanced tree size, the indices of the leaves to prove and a mutation seed from
+/// fuzz input. Sizes that are a power of two are bumped by one so the tree always has a ragged
+/// right edge.
+fn fuzz_to_multi_proof_input(input: &[u8]) -> Option<(usize, Vec<usize>, u64)> {
+    let (num_leaves, input) = input.split_first_chunk::<2>()?;
+    let (seed, input) = input.split_first_chunk::<8>()?;
+
+    let mut num_leaves = u16::from_le_bytes(*num_leaves) as usize % MAX_LEAVES + 2;
+    if num_leaves.is_power_of_two() {
+        num_leaves += 1;
+    }
+    let indices = input
+        .chunks_exact(2)
+        .map(|index| u16::from_le_bytes([index[0], index[1]]) as usize % num_leaves)
+        .collect::<Vec<_>>();
+
+    if indices.is_empty() {
+        return None
+    }
+
+    Some((num_leaves, indices, u64::from_le_bytes(*seed)))
+}
+
+fn calculate_root(
+    runner: &mut EvmRunner,
+    contract: Address,
+    proof: &MultiProof,
+) -> Result<[u8; 32], String> {
+    let call = CalculateRootCall {
+        proof: proof.proof.proof_hashes.iter().map(|hash| FixedBytes(hash.0)).collect(),
+        leaves: proof
+            .proof
+            .leaves
+            .iter()
+            .map(|leaf| MpLeaf { index: U256::from(leaf.index), hash: FixedBytes(leaf.hash.0) })
+            .collect(),
+        numLeaves: U256::from(proof.num_leaves),
+    };
+    Ok(runner.call_sol(contract, call)?._0.0)
+}
+
+fn setup() -> (EvmRunner, Address) {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let contract = runner.deploy(&project, "MerkleMultiProofTest");
+    (runner, contract)
+}
+
+pub fn fuzz_that_multi_proof_accepts_valid_proofs(input: &[u8]) {
+    let Some((num_leaves, indices, _)) = fuzz_to_multi_proof_input(input) else { return };
+    let proof = generate_multi_proof(num_leaves, &indices);
+
+    let (mut runner, contract) = setup();
+    let root = calculate_root(&mut runner, contract, &proof)
+        .expect("CalculateRoot should not revert for a valid proof");
+
+    assert_eq!(root, proof.root.0, "root mismatch for num_leaves={num_leaves}");
+}
+
+pub fn fuzz_that_multi_proof_rejects_invalid_proofs(input: &[u8]) {
+    let Some((num_leaves, indices, seed)) = fuzz_to_multi_proof_input(input) else { return };
+    let proof = generate_multi_proof(num_leaves, &indices);
+    // the known weaknesses of the verifier are left to its ignored mutation tests
+    let mutations = Mutation::all_except(MULTI_PROOF_WEAKNESSES);
+    let mutation = mutations[(seed % mutations.len() as u64) as usize];
+
+    let (mut runner, contract) = setup();
+    let report = check_mutations(&proof, &[mutation], &[], seed, |mutated| {
+        calculate_root(&mut runner, contract, mutated).is_ok_and(|root| root == proof.root.0)
+    });
+
+    assert!(report.accepted.is_empty(), "{mutation:?} verified for num_leaves={num_leaves}");
+}
@@ -0,0 +1,278 @@
+use crate::TrieInput;
+use alloy_primitives::Address;
+use alloy_sol_types::sol;
+use arbitrary::Arbitrary;
+use solidity_merkle_trees_test::{
+    evm_runner::{project_root, EvmRunner},
+    trie_utils::generate_trie_proof,
+};
+use sp_core::KeccakHasher;
+use sp_trie::{LayoutV0, LayoutV1, NodeCodec};
+use trie_db::{
+    node::{Node, NodeHandle, Value},
+    NibbleSlice, NodeCodec as NodeCodecT,
+};
+
+sol! {
+    struct SolNibbleSlice {
+        bytes data;
+        uint256 offset;
+    }
+
+    struct SolByteSlice {
+        bytes data;
+        uint256 offset;
+    }
+
+    struct SolNodeHandle {
+        bool isHash;
+        bytes32 hash;
+        bool isInline;
+        bytes inLine;
+    }
+
+    struct SolNodeHandleOption {
+        bool isSome;
+        SolNodeHandle value;
+    }
+
+    struct SolNodeKind {
+        bool isEmpty;
+        bool isLeaf;
+        bool isHashedLeaf;
+        bool isNibbledValueBranch;
+        bool isNibbledHashedValueBranch;
+        bool isNibbledBranch;
+        bool isExtension;
+        bool isBranch;
+        uint256 nibbleSize;
+        SolByteSlice data;
+    }
+
+    struct SolNibbledBranch {
+        SolNibbleSlice key;
+        SolNodeHandleOption value;
+        SolNodeHandleOption[16] children;
+    }
+
+    struct SolLeaf {
+        SolNibbleSlice key;
+        SolNodeHandle value;
+    }
+
+    function decodeNodeKind(bytes node) external pure returns (SolNodeKind);
+    function decodeNibbledBranch(bytes node) external pure returns (SolNibbledBranch);
+    function decodeLeaf(bytes node) external pure returns (SolLeaf);
+}
+
+/// Input for the node decoder fuzz target.
+#[derive(Debug, Arbitrary)]
+pub enum NodeInput {
+    /// Arbitrary bytes, which the decoders must either reject cleanly or decode like sp_trie.
+    Raw(Vec<u8>),
+    /// A trie whose proof nodes must all decode like sp_trie, built with LayoutV1 when
+    /// `hashed_values` is set so that values over 32 bytes are stored by hash.
+    Trie { trie: TrieInput, hashed_values: bool },
+}
+
+/// A reference to a value or child node.
+#[derive(Debug, PartialEq)]
+enum Handle {
+    Hash(Vec<u8>),
+    Inline(Vec<u8>),
+}
+
+/// A decoded node, with the partial key expanded to one nibble per byte.
+#[derive(Debug, PartialEq)]
+enum DecodedNode {
+    Empty,
+    Leaf { partial: Vec<u8>, value: Handle },
+    Branch { partial: Vec<u8>, value: Option<Handle>, children: Vec<Option<Handle>> },
+}
+
+fn rust_nibbles(slice: NibbleSlice) -> Vec<u8> {
+    (0..slice.len()).map(|i| slice.at(i)).collect()
+}
+
+fn solidity_nibbles(slice: &SolNibbleSlice) -> Vec<u8> {
+    let offset = slice.offset.to::<usize>();
+    (offset..slice.data.len() * 2)
+        .map(|i| if i % 2 == 0 { slice.data[i / 2] >> 4 } else { slice.data[i / 2] & 0x0f })
+        .collect()
+}
+
+fn solidity_handle(handle: &SolNodeHandle) -> Handle {
+    assert_ne!(handle.isHash, handle.isInline, "handle must be either a hash or inline");
+    if handle.isHash {
+        Handle::Hash(handle.hash.to_vec())
+    } else {
+        Handle::Inline(handle.inLine.to_vec())
+    }
+}
+
+fn solidity_handle_option(option: &SolNodeHandleOption) -> Option<Handle> {
+    option.isSome.then(|| solidity_handle(&option.value))
+}
+
+impl From<Node<'_>> for DecodedNode {
+    fn from(node: Node) -> Self {
+        let value = |value| match value {
+            Value::Inline(data) => Handle::Inline(data.to_vec()),
+            Value::Node(hash) => Handle::Hash(hash.to_vec()),
+        };
+        let child = |child: Option<NodeHandle>| {
+            child.map(|child| match child {
+                NodeHandle::Hash(hash) => Handle::Hash(hash.to_vec()),
+                NodeHandle::Inline(data) => Handle::Inline(data.to_vec()),
+            })
+        };
+
+        match node {
+            Node::Empty => DecodedNode::Empty,
+            Node::Leaf(partial, data) =>
+                DecodedNode::Leaf { partial: rust_nibbles(partial), value: value(data) },
+            Node::NibbledBranch(partial, children, data) => DecodedNode::Branch {
+                partial: rust_nibbles(partial),
+                value: data.map(value),
+                children: children.into_iter().map(child).collect(),
+            },
+            Node::Extension(..) | Node::Branch(..) =>
+                unreachable!("substrate tries have no extension nodes"),
+        }
+    }
+}
+
+/// Decode `node` with the Solidity decoder for the kind reported by `decodeNodeKind`.
+fn solidity_decode(
+    runner: &mut EvmRunner,
+    contract: Address,
+    kind: &SolNodeKind,
+    node: &[u8],
+) -> Result<DecodedNode, String> {
+    if kind.isEmpty {
+        return Ok(DecodedNode::Empty)
+    }
+
+    if kind.isLeaf || kind.isHashedLeaf {
+        let call = decodeLeafCall { node: node.to_vec().into() };
+        let leaf = runner.call_sol(contract, call)?._0;
+        assert_eq!(leaf.value.isHash, kind.isHashedLeaf, "leaf value disagrees with node kind");
+        return Ok(DecodedNode::Leaf {
+            partial: solidity_nibbles(&leaf.key),
+            value: solidity_handle(&leaf.value),
+        })
+    }
+
+    let call = decodeNibbledBranchCall { node: node.to_vec().into() };
+    let branch = runner.call_sol(contract, call)?._0;
+    let value = solidity_handle_option(&branch.value);
+    match &value {
+        None => assert!(kind.isNibbledBranch, "branch value disagrees with node kind"),
+        Some(Handle::Inline(_)) =>
+            assert!(kind.isNibbledValueBranch, "branch value disagrees with node kind"),
+        Some(Handle::Hash(_)) =>
+            assert!(kind.isNibbledHashedValueBranch, "branch value disagrees with node kind"),
+    }
+    Ok(DecodedNode::Branch {
+        partial: solidity_nibbles(&branch.key),
+        value,
+        children: branch.children.iter().map(solidity_handle_option).collect(),
+    })
+}
+
+/// Whether sp_trie decodes `node` without panicking, reading its header the way sp_trie does. It
+/// indexes the padding byte of an odd partial key without a bounds check, and reads header sizes
+/// past the u16::MAX at which the Solidity decoder stops, so those inputs are left to the Solidity
+/// side.
+fn rust_safe(node: &[u8]) -> bool {
+    let Some(&first) = node.first() else { return true };
+    // the bits taken by the prefix of each kind of node, besides the empty node and headers
+    // sp_trie rejects before reading a size
+    let prefix_bits = match first {
+        0 => return true,
+        _ if first >> 6 != 0 => 2,
+        _ if first >> 5 == 1 => 3,
+        _ if first >> 4 == 1 => 4,
+        _ => return true,
+    };
+    let max = 0xff >> prefix_bits;
+    let mut size = (first & max) as usize;
+    let mut offset = 1;
+    if size == max as usize {
+        size -= 1;
+        loop {
+            if size >= u16::MAX as usize {
+                return false
+            }
+            let Some(&byte) = node.get(offset) else { return true };
+            offset += 1;
+            if byte < 255 {
+                size += byte as usize + 1;
+                break
+            }
+            size += 255;
+        }
+    }
+    !(size % 2 == 1 && offset >= node.len()) && size < u16::MAX as usize
+}
+
+/// Decode `node` with both decoders and check that they agree. The Solidity decoders may revert
+/// on nodes that sp_trie rejects, and may also accept non-canonical encodings that sp_trie
+/// rejects (branches without children), since proof nodes are bound by their hash. `must_decode`
+/// is set for nodes taken from a real trie.
+fn check_node(runner: &mut EvmRunner, contract: Address, node: &[u8], must_decode: bool) {
+    let rust = || NodeCodec::<KeccakHasher>::decode(node).map(DecodedNode::from);
+    let rust_safe = rust_safe(node);
+
+    let call = decodeNodeKindCall { node: node.to_vec().into() };
+    let kind = match runner.call_sol(contract, call) {
+        Ok(kind) => kind._0,
+        Err(e) => {
+            assert!(e.starts_with("reverted"), "decodeNodeKind did not revert cleanly: {e}");
+            assert!(!must_decode, "decodeNodeKind rejected {node:?}: {e}");
+            if rust_safe {
+                assert!(rust().is_err(), "decodeNodeKind rejected {node:?}, sp_trie did not");
+            }
+            return
+        },
+    };
+
+    match solidity_decode(runner, contract, &kind, node) {
+        Ok(decoded) =>
+            if rust_safe {
+                if let Ok(expected) = rust() {
+                    assert_eq!(decoded, expected, "decoders disagree on {node:?}");
+                }
+            },
+        Err(e) => {
+            assert!(e.starts_with("reverted"), "decoder did not revert cleanly: {e}");
+            assert!(!must_decode, "decoder rejected {node:?}: {e}");
+            if rust_safe {
+                assert!(rust().is_err(), "decoder rejected {node:?}, sp_trie did not");
+            }
+        },
+    }
+}
+
+pub fn fuzz_that_node_decoders_match_sp_trie(input: NodeInput) {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let contract = runner.deploy(&project, "MerklePatriciaTest");
+
+    match input {
+        NodeInput::Raw(node) => check_node(&mut runner, contract, &node, false),
+        NodeInput::Trie { trie, hashed_values } => {
+            let (entries, absent) = trie.entries_and_keys();
+            // prove every entry so that every node of the trie is checked
+            let keys = entries.iter().map(|(key, _)| key.clone()).chain(absent).collect::<Vec<_>>();
+            let proof = if hashed_values {
+                generate_trie_proof::<LayoutV1<KeccakHasher>>(&entries, &keys).proof
+            } else {
+                generate_trie_proof::<LayoutV0<KeccakHasher>>(&entries, &keys).proof
+            };
+            for node in proof {
+                check_node(&mut runner, contract, &node, true);
+            }
+        },
+    }
+}
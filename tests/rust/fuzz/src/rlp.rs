@@ -0,0 +1,77 @@
+use crate::call_or_revert;
+use alloy_primitives::U256;
+use alloy_rlp::{Decodable, Header};
+use alloy_sol_types::sol;
+use solidity_merkle_trees_test::evm_runner::{project_root, EvmRunner};
+
+sol! {
+    function isList(bytes encoded) external pure returns (bool);
+    function payloadLen(bytes encoded) external pure returns (uint256);
+    function toBytes(bytes encoded) external pure returns (bytes);
+    function toUint(bytes encoded) external pure returns (uint256);
+    function toUintStrict(bytes encoded) external pure returns (uint256);
+    function toList(bytes encoded) external pure returns (uint256[] offsets, uint256[] lengths);
+}
+
+/// The offset and length of each item in `payload`, if every item is canonically encoded.
+fn item_extents(header_len: usize, mut payload: &[u8]) -> Option<Vec<(usize, usize)>> {
+    let mut extents = Vec::new();
+    let mut offset = header_len;
+    while !payload.is_empty() {
+        let before = payload.len();
+        let header = Header::decode(&mut payload).ok()?;
+        payload = &payload[header.payload_length..];
+        let len = before - payload.len();
+        extents.push((offset, len));
+        offset += len;
+    }
+    Some(extents)
+}
+
+/// Feed arbitrary bytes to RLPReader. It must never halt, it must reject an item followed by
+/// trailing bytes, and wherever alloy-rlp decodes the input as a single item the two must agree.
+pub fn fuzz_that_rlp_reader_matches_alloy_rlp(input: &[u8]) {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let contract = runner.deploy(&project, "RLPReaderTest");
+    let encoded = || input.to_vec().into();
+
+    let is_list = call_or_revert(&mut runner, contract, isListCall { encoded: encoded() });
+    let payload_len = call_or_revert(&mut runner, contract, payloadLenCall { encoded: encoded() });
+    let bytes = call_or_revert(&mut runner, contract, toBytesCall { encoded: encoded() });
+    let uint = call_or_revert(&mut runner, contract, toUintCall { encoded: encoded() });
+    let uint_strict =
+        call_or_revert(&mut runner, contract, toUintStrictCall { encoded: encoded() });
+    let list = call_or_revert(&mut runner, contract, toListCall { encoded: encoded() });
+
+    let buf = &mut &input[..];
+    let Ok(header) = Header::decode(buf) else { return };
+    if buf.len() != header.payload_length {
+        // followed by trailing bytes
+        assert!(is_list.is_none() && payload_len.is_none() && bytes.is_none() && list.is_none());
+        return
+    }
+    let header_len = input.len() - buf.len();
+
+    assert_eq!(is_list.map(|r| r._0), Some(header.list));
+    assert_eq!(payload_len.map(|r| r._0), Some(U256::from(header.payload_length)));
+    assert_eq!(bytes.map(|r| r._0.to_vec()), Some(buf.to_vec()));
+
+    if header.list {
+        if let Some(expected) = item_extents(header_len, buf) {
+            let list = list.expect("toList reverted on a canonical list");
+            let extents = list
+                .offsets
+                .iter()
+                .zip(&list.lengths)
+                .map(|(offset, len)| (offset.to::<usize>(), len.to::<usize>()))
+                .collect::<Vec<_>>();
+            assert_eq!(extents, expected);
+        }
+    } else if let Ok(value) = U256::decode(&mut &input[..]) {
+        assert_eq!(uint.map(|r| r._0), Some(value));
+        if input.len() == 33 {
+            assert_eq!(uint_strict.map(|r| r._0), Some(value));
+        }
+    }
+}
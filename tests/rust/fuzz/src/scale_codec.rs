@@ -0,0 +1,55 @@
+use crate::call_or_revert;
+use alloy_primitives::U256;
+use alloy_sol_types::sol;
+use codec::{Compact, Decode, Encode};
+use solidity_merkle_trees_test::evm_runner::{project_root, EvmRunner};
+
+sol! {
+    function decodeUint256(bytes data) external pure returns (uint256);
+    function decodeUintCompact(bytes data) external pure returns (uint256 value, uint256 offset);
+    function decodeUintCompactWithMode(bytes data) external pure returns (uint256 value, uint8 mode);
+    function encodeUintCompact(uint256 v) external pure returns (bytes);
+    function encodeBytes(bytes input) external pure returns (bytes);
+}
+
+/// Feed arbitrary bytes to ScaleCodec. It must never halt, and wherever parity-scale-codec
+/// decodes the input the two must agree.
+pub fn fuzz_that_scale_codec_matches_parity_scale_codec(input: &[u8]) {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let contract = runner.deploy(&project, "ScaleCodecTest");
+    let data = || input.to_vec().into();
+
+    let compact = call_or_revert(&mut runner, contract, decodeUintCompactCall { data: data() });
+    let with_mode =
+        call_or_revert(&mut runner, contract, decodeUintCompactWithModeCall { data: data() });
+    let uint = call_or_revert(&mut runner, contract, decodeUint256Call { data: data() });
+    let bytes = call_or_revert(&mut runner, contract, encodeBytesCall { input: data() });
+
+    let buf = &mut &input[..];
+    if let Ok(Compact(value)) = <Compact<u64>>::decode(buf) {
+        let consumed = input.len() - buf.len();
+        let compact = compact.expect("decodeUintCompact reverted on a canonical compact");
+        assert_eq!((compact.value, compact.offset.to::<usize>()), (U256::from(value), consumed));
+
+        // the `bytes` overload only decodes the single, two and four byte modes
+        if let Some(with_mode) = with_mode.filter(|r| r.mode < 3) {
+            assert_eq!(with_mode.value, U256::from(value));
+        }
+    }
+
+    match input.len() {
+        0..=32 => assert_eq!(uint.map(|r| r._0), Some(U256::from_le_slice(input))),
+        _ => assert!(uint.is_none()),
+    }
+
+    assert_eq!(bytes.map(|r| r._0.to_vec()), Some(input.encode()));
+
+    let mut word = [0u8; 16];
+    let len = input.len().min(16);
+    word[..len].copy_from_slice(&input[..len]);
+    let value = u128::from_le_bytes(word);
+    let encoded =
+        call_or_revert(&mut runner, contract, encodeUintCompactCall { v: U256::from(value) });
+    assert_eq!(encoded.map(|r| r._0.to_vec()), Some(Compact(value).encode()));
+}
@@ -0,0 +1,159 @@
+#![cfg(test)]
+
+use crate::{
+    merkle_patricia::{
+        assert_values, blake2b256Call, generate_proof, setup, ReadChildBlake2Call,
+        VerifyKeysBlake2Call, VerifyKeysCall,
+    },
+    seeded_rng,
+    state_proof::{
+        self, expand_compact_proof,
+        tests::{arb_state, read_keys},
+    },
+    trie_utils::generate_trie_proof,
+};
+use alloy_primitives::{keccak256, FixedBytes};
+use alloy_sol_types::SolCall;
+use codec::Encode;
+use rand::Rng;
+use sp_core::{
+    hashing::blake2_256,
+    storage::{ChildInfo, StateVersion},
+    KeccakHasher,
+};
+use sp_runtime::traits::BlakeTwo256;
+use sp_trie::{LayoutV0, LayoutV1, StorageProof};
+use trie_db::TrieLayout;
+
+#[test]
+fn test_blake2b_256() {
+    let mut rng = seeded_rng();
+    let (mut runner, addr) = setup();
+
+    // lengths either side of the 128 byte blocks and of the words they are copied in
+    for len in [0usize, 1, 31, 32, 33, 127, 128, 129, 255, 256, 257, 1000] {
+        let data = (0..len).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+        let call = blake2b256Call { data: data.clone().into() };
+        let result = runner.call_raw(addr, call.abi_encode());
+        let hash = blake2b256Call::abi_decode_returns(&result, true).unwrap()._0;
+        assert_eq!(hash.0, blake2_256(&data), "input of {len} bytes");
+    }
+}
+
+fn verify_blake2_proof<L: TrieLayout<Hash = BlakeTwo256>>() {
+    let mut rng = seeded_rng();
+    let (root, proof, items) = generate_proof::<L>(&mut rng);
+    let mut keys = items.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+    keys.push(rng.gen::<[u8; 32]>().to_vec());
+    let (mut runner, addr) = setup();
+
+    let call = VerifyKeysBlake2Call {
+        root: FixedBytes(root.0),
+        proof: proof.iter().cloned().map(Into::into).collect(),
+        keys: keys.iter().cloned().map(Into::into).collect(),
+    };
+    let result = runner.call_raw(addr, call.abi_encode());
+    let values = VerifyKeysBlake2Call::abi_decode_returns(&result, true).unwrap()._0;
+    assert_values(&values[..items.len()], &items);
+    assert!(values[items.len()].value.is_empty());
+
+    // the keccak256 verifier finds none of the nodes it looks up by hash
+    let call = VerifyKeysCall {
+        root: FixedBytes(root.0),
+        proof: proof.iter().cloned().map(Into::into).collect(),
+        keys: keys.iter().cloned().map(Into::into).collect(),
+    };
+    assert!(runner.call_may_revert(addr, call.abi_encode()).is_err());
+}
+
+#[test]
+fn test_merkle_patricia_trie_blake2_layout_v0() {
+    verify_blake2_proof::<LayoutV0<BlakeTwo256>>();
+}
+
+#[test]
+fn test_merkle_patricia_trie_blake2_layout_v1() {
+    verify_blake2_proof::<LayoutV1<BlakeTwo256>>();
+}
+
+#[test]
+fn test_state_machine_blake2_child_read_proof() {
+    let mut rng = seeded_rng();
+    let child_info = ChildInfo::new_default(b"child");
+    let (mut runner, addr) = setup();
+
+    for state_version in [StateVersion::V0, StateVersion::V1] {
+        let state = arb_state(&mut rng, &child_info);
+        let backend = state.backend::<BlakeTwo256>(state_version);
+        let keys = read_keys(&mut rng, &state.children[0].1);
+        let proof = state_proof::prove_child_read(&backend, &child_info, &keys);
+        let compact = StorageProof::new(proof.proof.clone())
+            .into_compact_proof::<BlakeTwo256>(proof.root)
+            .unwrap();
+        let nodes = expand_compact_proof::<BlakeTwo256>(&compact.encode(), proof.root).unwrap();
+
+        let call = ReadChildBlake2Call {
+            root: FixedBytes(proof.root.0),
+            proof: nodes.into_iter().map(Into::into).collect(),
+            keys: keys.iter().cloned().map(Into::into).collect(),
+            childInfo: child_info.storage_key().to_vec().into(),
+        };
+        let result = runner.call_raw(addr, call.abi_encode());
+        let values = ReadChildBlake2Call::abi_decode_returns(&result, true).unwrap()._0;
+        assert_values(&values, &proof.items);
+    }
+}
+
+/// Gas of the keccak256 and blake2b-256 verifiers for tries of the same entries, which differ only
+/// in the hashes of their nodes. Run with `--ignored --nocapture` to print the measurements.
+#[test]
+#[ignore]
+fn test_blake2_substrate_gas_comparison() {
+    let (mut runner, addr) = setup();
+
+    for trie_size in [16usize, 256, 4096] {
+        for keys_per_proof in [1usize, 16] {
+            // values of 64 bytes are hashed too
+            let entries = (0..trie_size)
+                .map(|i| (keccak256((i as u32).to_le_bytes()).to_vec(), vec![i as u8; 64]))
+                .collect::<Vec<_>>();
+            let keys = entries
+                .iter()
+                .step_by(trie_size / keys_per_proof)
+                .take(keys_per_proof)
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>();
+            let keccak = generate_trie_proof::<LayoutV1<KeccakHasher>>(&entries, &keys);
+            let blake2 = generate_trie_proof::<LayoutV1<BlakeTwo256>>(&entries, &keys);
+            assert_eq!(keccak.proof.len(), blake2.proof.len());
+
+            let call = VerifyKeysCall {
+                root: FixedBytes(keccak.root.0),
+                proof: keccak.proof.iter().cloned().map(Into::into).collect(),
+                keys: keys.iter().cloned().map(Into::into).collect(),
+            };
+            let (result, keccak_gas) = runner.call_with_gas(addr, call.abi_encode());
+            let values = VerifyKeysCall::abi_decode_returns(&result, true).unwrap()._0;
+            assert_values(&values, &keccak.items);
+
+            let call = VerifyKeysBlake2Call {
+                root: FixedBytes(blake2.root.0),
+                proof: blake2.proof.iter().cloned().map(Into::into).collect(),
+                keys: keys.iter().cloned().map(Into::into).collect(),
+            };
+            let (result, blake2_gas) = runner.call_with_gas(addr, call.abi_encode());
+            let values = VerifyKeysBlake2Call::abi_decode_returns(&result, true).unwrap()._0;
+            assert_values(&values, &blake2.items);
+
+            println!(
+                "entries={:>4}  keys={:>2}  proof_nodes={:>3}  keccak_gas={:>8}  blake2_gas={:>8}  ratio={:.2}",
+                trie_size,
+                keys_per_proof,
+                blake2.proof.len(),
+                keccak_gas,
+                blake2_gas,
+                blake2_gas as f64 / keccak_gas as f64
+            );
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Ethereum block headers, so that the trie roots proofs are verified against can be taken from a
+//! header whose hash is known, such as one verified by a light client.
+
+use alloy_primitives::{keccak256, Address, Bloom, Bytes, B256, B64, U256};
+use alloy_rlp::{Decodable, Encodable, Error, Header};
+
+/// The fork whose header format a header follows, each adding fields to the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HeaderFork {
+    Legacy,
+    /// EIP-1559 `baseFeePerGas`.
+    London,
+    /// EIP-4895 `withdrawalsRoot`.
+    Shanghai,
+    /// EIP-4844 `blobGasUsed` and `excessBlobGas`, and EIP-4788 `parentBeaconBlockRoot`.
+    Cancun,
+    /// EIP-7685 `requestsHash`.
+    Prague,
+}
+
+/// An Ethereum block header. Fields added by forks are present from the fork that added them on,
+/// and each of them requires the fields added before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub parent_hash: B256,
+    pub ommers_hash: B256,
+    pub beneficiary: Address,
+    pub state_root: B256,
+    pub transactions_root: B256,
+    pub receipts_root: B256,
+    pub logs_bloom: Bloom,
+    pub difficulty: U256,
+    pub number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Bytes,
+    pub mix_hash: B256,
+    pub nonce: B64,
+    pub base_fee_per_gas: Option<u64>,
+    pub withdrawals_root: Option<B256>,
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    pub parent_beacon_block_root: Option<B256>,
+    pub requests_hash: Option<B256>,
+}
+
+/// Decode the next field if any are left.
+fn decode_optional<T: Decodable>(buf: &mut &[u8]) -> Result<Option<T>, Error> {
+    if buf.is_empty() {
+        return Ok(None)
+    }
+    T::decode(buf).map(Some)
+}
+
+impl BlockHeader {
+    /// Decode the RLP list of a header of any fork, requiring that nothing follows it.
+    pub fn decode(rlp: &[u8]) -> Result<Self, Error> {
+        let buf = &mut &rlp[..];
+        let list = Header::decode(buf)?;
+        if !list.list {
+            return Err(Error::UnexpectedString)
+        }
+        if buf.len() != list.payload_length {
+            return Err(Error::UnexpectedLength)
+        }
+
+        let header = Self {
+            parent_hash: B256::decode(buf)?,
+            ommers_hash: B256::decode(buf)?,
+            beneficiary: Address::decode(buf)?,
+            state_root: B256::decode(buf)?,
+            transactions_root: B256::decode(buf)?,
+            receipts_root: B256::decode(buf)?,
+            logs_bloom: Bloom::decode(buf)?,
+            difficulty: U256::decode(buf)?,
+            number: u64::decode(buf)?,
+            gas_limit: u64::decode(buf)?,
+            gas_used: u64::decode(buf)?,
+            timestamp: u64::decode(buf)?,
+            extra_data: Bytes::decode(buf)?,
+            mix_hash: B256::decode(buf)?,
+            nonce: B64::decode(buf)?,
+            base_fee_per_gas: decode_optional(buf)?,
+            withdrawals_root: decode_optional(buf)?,
+            blob_gas_used: decode_optional(buf)?,
+            excess_blob_gas: decode_optional(buf)?,
+            parent_beacon_block_root: decode_optional(buf)?,
+            requests_hash: decode_optional(buf)?,
+        };
+        if !buf.is_empty() {
+            return Err(Error::Custom("unknown header fields"))
+        }
+        // Cancun added its three fields at once
+        if header.blob_gas_used.is_some() && header.parent_beacon_block_root.is_none() {
+            return Err(Error::Custom("incomplete Cancun header fields"))
+        }
+        Ok(header)
+    }
+
+    /// Decode a header, requiring that it hashes to `hash`, so that its roots can be trusted as
+    /// much as the hash is.
+    pub fn decode_verified(rlp: &[u8], hash: B256) -> Result<Self, Error> {
+        if keccak256(rlp) != hash {
+            return Err(Error::Custom("header does not hash to the block hash"))
+        }
+        Self::decode(rlp)
+    }
+
+    /// The RLP encoding of the header, with the fields added by forks up to the first one absent.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.parent_hash.encode(&mut payload);
+        self.ommers_hash.encode(&mut payload);
+        self.beneficiary.encode(&mut payload);
+        self.state_root.encode(&mut payload);
+        self.transactions_root.encode(&mut payload);
+        self.receipts_root.encode(&mut payload);
+        self.logs_bloom.encode(&mut payload);
+        self.difficulty.encode(&mut payload);
+        self.number.encode(&mut payload);
+        self.gas_limit.encode(&mut payload);
+        self.gas_used.encode(&mut payload);
+        self.timestamp.encode(&mut payload);
+        self.extra_data.encode(&mut payload);
+        self.mix_hash.encode(&mut payload);
+        self.nonce.encode(&mut payload);
+
+        let optional: [Option<&dyn Encodable>; 6] = [
+            self.base_fee_per_gas.as_ref().map(|field| field as &dyn Encodable),
+            self.withdrawals_root.as_ref().map(|field| field as &dyn Encodable),
+            self.blob_gas_used.as_ref().map(|field| field as &dyn Encodable),
+            self.excess_blob_gas.as_ref().map(|field| field as &dyn Encodable),
+            self.parent_beacon_block_root.as_ref().map(|field| field as &dyn Encodable),
+            self.requests_hash.as_ref().map(|field| field as &dyn Encodable),
+        ];
+        for field in optional.into_iter().map_while(|field| field) {
+            field.encode(&mut payload);
+        }
+
+        let mut out = Vec::new();
+        Header { list: true, payload_length: payload.len() }.encode(&mut out);
+        out.extend(payload);
+        out
+    }
+
+    /// The block hash.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.encode())
+    }
+
+    /// The latest fork whose fields the header has.
+    pub fn fork(&self) -> HeaderFork {
+        if self.requests_hash.is_some() {
+            HeaderFork::Prague
+        } else if self.parent_beacon_block_root.is_some() {
+            HeaderFork::Cancun
+        } else if self.withdrawals_root.is_some() {
+            HeaderFork::Shanghai
+        } else if self.base_fee_per_gas.is_some() {
+            HeaderFork::London
+        } else {
+            HeaderFork::Legacy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eip1186::GetProofResponse,
+        eth_rpc::{self, tests::rpc_genesis},
+        ethereum_storage::verify_ethereum_storage,
+        seeded_rng,
+    };
+    use hex_literal::hex;
+    use rand::Rng;
+
+    #[test]
+    fn test_block_header_mainnet_genesis() {
+        let genesis = BlockHeader {
+            ommers_hash: hex!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")
+                .into(),
+            state_root: hex!("d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544")
+                .into(),
+            transactions_root: hex!(
+                "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+            )
+            .into(),
+            receipts_root: hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+                .into(),
+            difficulty: U256::from(0x400000000u64),
+            gas_limit: 5000,
+            extra_data: hex!("11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa")
+                .to_vec()
+                .into(),
+            nonce: hex!("0000000000000042").into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            genesis.hash(),
+            B256::from(hex!("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"))
+        );
+        assert_eq!(BlockHeader::decode(&genesis.encode()).unwrap(), genesis);
+        assert_eq!(genesis.fork(), HeaderFork::Legacy);
+    }
+
+    /// A header with random fields, having the fields of every fork up to `fork`.
+    fn arb_block_header(rng: &mut impl Rng, fork: HeaderFork) -> BlockHeader {
+        let mut bloom = [0u8; 256];
+        rng.fill(&mut bloom[..]);
+        let hash = |rng: &mut dyn rand::RngCore| B256::from(rng.gen::<[u8; 32]>());
+        BlockHeader {
+            parent_hash: hash(rng),
+            ommers_hash: hash(rng),
+            beneficiary: Address::from(rng.gen::<[u8; 20]>()),
+            state_root: hash(rng),
+            transactions_root: hash(rng),
+            receipts_root: hash(rng),
+            logs_bloom: bloom.into(),
+            difficulty: if fork < HeaderFork::Shanghai {
+                U256::from(rng.gen::<u64>())
+            } else {
+                U256::ZERO
+            },
+            number: rng.gen::<u32>().into(),
+            gas_limit: rng.gen::<u32>().into(),
+            gas_used: rng.gen::<u32>().into(),
+            timestamp: rng.gen::<u32>().into(),
+            extra_data: (0..rng.gen_range(0..=32))
+                .map(|_| rng.gen::<u8>())
+                .collect::<Vec<_>>()
+                .into(),
+            mix_hash: hash(rng),
+            nonce: rng.gen::<[u8; 8]>().into(),
+            base_fee_per_gas: (fork >= HeaderFork::London).then(|| rng.gen::<u32>().into()),
+            withdrawals_root: (fork >= HeaderFork::Shanghai).then(|| hash(rng)),
+            blob_gas_used: (fork >= HeaderFork::Cancun).then(|| rng.gen::<u32>().into()),
+            excess_blob_gas: (fork >= HeaderFork::Cancun).then(|| rng.gen::<u32>().into()),
+            parent_beacon_block_root: (fork >= HeaderFork::Cancun).then(|| hash(rng)),
+            requests_hash: (fork >= HeaderFork::Prague).then(|| hash(rng)),
+        }
+    }
+
+    #[test]
+    fn test_block_header_forks() {
+        let mut rng = seeded_rng();
+        let forks = [
+            HeaderFork::Legacy,
+            HeaderFork::London,
+            HeaderFork::Shanghai,
+            HeaderFork::Cancun,
+            HeaderFork::Prague,
+        ];
+
+        for fork in forks {
+            let header = arb_block_header(&mut rng, fork);
+            let encoded = header.encode();
+            let decoded = BlockHeader::decode(&encoded).unwrap();
+            assert_eq!(decoded, header, "fork={fork:?}");
+            assert_eq!(decoded.fork(), fork);
+            assert_eq!(decoded.hash(), keccak256(&encoded));
+            assert_eq!(
+                BlockHeader::decode_verified(&encoded, keccak256(&encoded)).unwrap(),
+                header
+            );
+            assert!(BlockHeader::decode_verified(&encoded, header.parent_hash).is_err());
+
+            let mut trailing = encoded.clone();
+            trailing.push(0x80);
+            assert!(BlockHeader::decode(&trailing).is_err());
+        }
+    }
+
+    /// Append `field` to the RLP list `encoded`.
+    fn append_rlp_field(encoded: &[u8], field: impl alloy_rlp::Encodable) -> Vec<u8> {
+        let buf = &mut &encoded[..];
+        alloy_rlp::Header::decode(buf).unwrap();
+        let mut payload = buf.to_vec();
+        field.encode(&mut payload);
+        let mut list = Vec::new();
+        alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(&mut list);
+        list.extend(payload);
+        list
+    }
+
+    #[test]
+    fn test_block_header_rejects_malformed_headers() {
+        let mut rng = seeded_rng();
+
+        let prague = arb_block_header(&mut rng, HeaderFork::Prague);
+        let unknown_field = append_rlp_field(&prague.encode(), B256::ZERO);
+        assert!(BlockHeader::decode(&unknown_field).is_err());
+
+        // Cancun's fields come together
+        let incomplete = BlockHeader {
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            ..arb_block_header(&mut rng, HeaderFork::Cancun)
+        };
+        assert!(BlockHeader::decode(&incomplete.encode()).is_err());
+
+        let legacy = arb_block_header(&mut rng, HeaderFork::Legacy).encode();
+        assert!(BlockHeader::decode(&legacy[..legacy.len() - 1]).is_err());
+        assert!(BlockHeader::decode(&alloy_rlp::encode(legacy.as_slice())).is_err());
+    }
+
+    /// A header whose hash is trusted vouches for its state root, and through it for the proofs of
+    /// accounts and storage against that root.
+    #[test]
+    fn test_block_header_ties_proofs_to_block_hash() {
+        let mut rng = seeded_rng();
+        let contract = Address::from(rng.gen::<[u8; 20]>());
+        let state =
+            eth_rpc::State::from_genesis(&rpc_genesis(&mut rng, contract).to_string()).unwrap();
+        let header = BlockHeader {
+            state_root: state.state_root(),
+            ..arb_block_header(&mut rng, HeaderFork::Cancun)
+        };
+        let block_hash = header.hash();
+
+        let header = BlockHeader::decode_verified(&header.encode(), block_hash).unwrap();
+        let slot = *state.accounts()[&contract].storage.keys().next().unwrap();
+        let response = state.get_proof(&contract, &[slot]).to_string();
+        let parsed = GetProofResponse::parse(&response, header.state_root).unwrap();
+        let verified = verify_ethereum_storage(
+            header.state_root,
+            contract,
+            &parsed.account_proof.proof,
+            slot,
+            &parsed.storage_proofs[0].proof.proof,
+        )
+        .unwrap();
+        assert_eq!(verified.value, state.storage_at(&contract, &slot));
+    }
+}
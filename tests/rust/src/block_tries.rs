@@ -0,0 +1,382 @@
+//! The transactions, receipts and withdrawals tries of a block, which key each item by the RLP
+//! encoding of its index in the block, so that proofs of inclusion for `VerifyEthereumProof` can
+//! be generated locally.
+
+use crate::{
+    ethereum_trie::EthereumLayout,
+    trie_utils::{generate_trie_proof, TrieProof},
+};
+use alloy_primitives::{Address, Bloom, Bytes, Log, B256};
+use alloy_rlp::{Decodable, Encodable, Error, Header};
+use alloy_sol_types::SolEvent;
+use primitive_types::H256;
+
+/// The key of the item at `index` in the transactions, receipts or withdrawals trie.
+pub fn index_key(index: usize) -> Vec<u8> {
+    alloy_rlp::encode(index)
+}
+
+/// The EIP-2718 encoding of an item of type `ty`, its type followed by its RLP `payload`, as held
+/// by the transactions and receipts tries. Legacy items, of type 0, are their payload alone.
+pub fn typed_encoding(ty: u8, payload: &[u8]) -> Vec<u8> {
+    match ty {
+        0 => payload.to_vec(),
+        ty => [&[ty][..], payload].concat(),
+    }
+}
+
+/// A withdrawal from the beacon chain, as introduced by EIP-4895.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: Address,
+    /// Amount in Gwei.
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// The RLP list of the withdrawal's fields held by the withdrawals trie.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.index.encode(&mut payload);
+        self.validator_index.encode(&mut payload);
+        self.address.encode(&mut payload);
+        self.amount.encode(&mut payload);
+
+        encode_list(payload)
+    }
+}
+
+/// A transaction receipt, in the format introduced by EIP-658 where the status of the transaction
+/// replaced the intermediate state root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    /// EIP-2718 transaction type, 0 for legacy transactions.
+    pub ty: u8,
+    pub success: bool,
+    pub cumulative_gas_used: u64,
+    pub logs_bloom: Bloom,
+    pub logs: Vec<Log>,
+}
+
+fn encode_list(payload: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::new();
+    Header { list: true, payload_length: payload.len() }.encode(&mut out);
+    out.extend(payload);
+    out
+}
+
+/// Decode the header of a list, returning its payload and advancing `buf` past it.
+fn decode_list<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(Error::UnexpectedString)
+    }
+    if buf.len() < header.payload_length {
+        return Err(Error::InputTooShort)
+    }
+    let (payload, rest) = buf.split_at(header.payload_length);
+    *buf = rest;
+    Ok(payload)
+}
+
+impl Receipt {
+    /// A receipt whose bloom filter is that of its logs.
+    pub fn new(ty: u8, success: bool, cumulative_gas_used: u64, logs: Vec<Log>) -> Self {
+        let mut logs_bloom = Bloom::default();
+        for log in &logs {
+            logs_bloom.accrue_log(log);
+        }
+        Self { ty, success, cumulative_gas_used, logs_bloom, logs }
+    }
+
+    /// The EIP-2718 encoding held by the receipts trie.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.success.encode(&mut payload);
+        self.cumulative_gas_used.encode(&mut payload);
+        self.logs_bloom.encode(&mut payload);
+        let mut logs = Vec::new();
+        for log in &self.logs {
+            let mut fields = Vec::new();
+            log.address.encode(&mut fields);
+            log.topics().to_vec().encode(&mut fields);
+            log.data.data.encode(&mut fields);
+            logs.extend(encode_list(fields));
+        }
+        payload.extend(encode_list(logs));
+        typed_encoding(self.ty, &encode_list(payload))
+    }
+
+    /// Decode a receipt from its EIP-2718 encoding, as proven in the receipts trie.
+    pub fn decode(encoded: &[u8]) -> Result<Self, Error> {
+        let (ty, mut buf) = match encoded.first() {
+            None => return Err(Error::InputTooShort),
+            Some(&ty) if ty < 0x80 => (ty, &encoded[1..]),
+            Some(_) => (0, encoded),
+        };
+        let mut payload = decode_list(&mut buf)?;
+        if !buf.is_empty() {
+            return Err(Error::UnexpectedLength)
+        }
+
+        let success = bool::decode(&mut payload)?;
+        let cumulative_gas_used = u64::decode(&mut payload)?;
+        let logs_bloom = Bloom::decode(&mut payload)?;
+        let mut list = decode_list(&mut payload)?;
+        if !payload.is_empty() {
+            return Err(Error::UnexpectedLength)
+        }
+
+        let mut logs = Vec::new();
+        while !list.is_empty() {
+            let mut fields = decode_list(&mut list)?;
+            let address = Address::decode(&mut fields)?;
+            let topics = Vec::<B256>::decode(&mut fields)?;
+            let data = Bytes::decode(&mut fields)?;
+            if !fields.is_empty() {
+                return Err(Error::UnexpectedLength)
+            }
+            logs.push(Log::new_unchecked(address, topics, data));
+        }
+
+        Ok(Self { ty, success, cumulative_gas_used, logs_bloom, logs })
+    }
+}
+
+/// Decode log `log_index` of the encoded `receipt` as the event `E`, requiring that it was emitted
+/// by `emitter` and that its topics are those of `E`: its signature, unless anonymous, followed
+/// by its indexed parameters.
+pub fn decode_receipt_event<E: SolEvent>(
+    receipt: &[u8],
+    log_index: usize,
+    emitter: Address,
+) -> Result<E, String> {
+    let receipt = Receipt::decode(receipt).map_err(|e| format!("invalid receipt: {e}"))?;
+    if !receipt.success {
+        return Err("the transaction reverted, so its receipt has no logs".to_string())
+    }
+    let log = receipt
+        .logs
+        .get(log_index)
+        .ok_or_else(|| format!("receipt has {} logs, not {}", receipt.logs.len(), log_index + 1))?;
+    if log.address != emitter {
+        return Err(format!("log was emitted by {}, not {emitter}", log.address))
+    }
+    E::decode_log_data(&log.data, true).map_err(|e| format!("log is not {}: {e}", E::SIGNATURE))
+}
+
+/// A trie of encoded items keyed by their index, such as a block's transactions, receipts or
+/// withdrawals.
+#[derive(Debug, Clone, Default)]
+pub struct IndexTrie {
+    pub items: Vec<Vec<u8>>,
+}
+
+impl IndexTrie {
+    /// A trie of already encoded items, such as EIP-2718 encoded transactions or receipts.
+    pub fn new(items: Vec<Vec<u8>>) -> Self {
+        Self { items }
+    }
+
+    pub fn withdrawals(withdrawals: &[Withdrawal]) -> Self {
+        Self::new(withdrawals.iter().map(Withdrawal::encode).collect())
+    }
+
+    fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (index_key(i), item.clone()))
+            .collect()
+    }
+
+    /// The root committed to by the block header.
+    pub fn root(&self) -> B256 {
+        B256::from(generate_trie_proof::<EthereumLayout>(&self.entries(), &[]).root.0)
+    }
+
+    /// The proof of the items at `indices` for `VerifyEthereumProof`, whose keys are
+    /// [`index_key`]s. Indices past the last item are proven absent.
+    pub fn prove(&self, indices: &[usize]) -> TrieProof<H256> {
+        let keys = indices.iter().map(|index| index_key(*index)).collect::<Vec<_>>();
+        generate_trie_proof::<EthereumLayout>(&self.entries(), &keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        evm_runner::{project_root, EvmRunner},
+        merkle_patricia::{ethereum_transaction_proof_data, setup, VerifyEthereumCall},
+        seeded_rng,
+    };
+    use alloy_primitives::{FixedBytes, U256};
+    use alloy_sol_types::{sol, SolCall};
+    use hex_literal::hex;
+    use rand::Rng;
+
+    sol! {
+        event Transfer(address indexed from, address indexed to, uint256 value);
+        event Message(uint256 indexed id, string text);
+
+        function transfer(address to, uint256 value) external;
+        function transferWithMessages(address to, uint256 value, string text) external;
+    }
+
+    #[test]
+    fn test_index_trie_roots() {
+        assert_eq!(
+            IndexTrie::default().root(),
+            B256::from(hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"))
+        );
+
+        // the single transaction of the canned single node proof
+        let (_, _, _, transaction) = ethereum_transaction_proof_data();
+        let trie = IndexTrie::new(vec![transaction]);
+        assert_eq!(
+            trie.root(),
+            B256::from(hex!("ecabc214ab6c55e1342e888fa677e2bcc29218a4b248a56fcebf7aa357807b60"))
+        );
+        let TrieProof { proof, .. } = trie.prove(&[0]);
+        assert_eq!(proof, vec![hex!("f89b822080b89601f89301808080808080f847f84580f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000080a08c7939f0e613736150a05565fcddda959b22c44ddac6c6aed8ec59e1462a0498a0166d30e3763829d64fca3d38601e65ba6f0e94f7e3c544381ae5e9e9b12dacd0").to_vec()]);
+    }
+
+    /// A transaction of type `ty` with random fields. It is not a valid transaction, but is encoded
+    /// like one. Small transactions, like those of devnets and local chains, have small fields and
+    /// no data, which keeps their leaves short enough to be embedded in their parent.
+    fn arb_typed_transaction(rng: &mut impl Rng, ty: u8, small: bool) -> Vec<u8> {
+        let (nonce, value, data_len) = if small {
+            (rng.gen_range(0..128u32), rng.gen_range(0..128u64), 0)
+        } else {
+            (rng.gen::<u32>(), rng.gen::<u64>(), rng.gen_range(32..96))
+        };
+        let mut payload = Vec::new();
+        alloy_rlp::Encodable::encode(&nonce, &mut payload);
+        alloy_rlp::Encodable::encode(&U256::from(value), &mut payload);
+        alloy_rlp::Encodable::encode(&Address::from(rng.gen::<[u8; 20]>()), &mut payload);
+        let data = (0..data_len).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+        alloy_rlp::Encodable::encode(data.as_slice(), &mut payload);
+
+        let mut list = Vec::new();
+        alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(&mut list);
+        list.extend(payload);
+        typed_encoding(ty, &list)
+    }
+
+    /// Verify the proof of `indices` in `trie` with `VerifyEthereum`, returning the proven values.
+    fn verify_index_trie(trie: &IndexTrie, indices: &[usize]) -> Vec<Vec<u8>> {
+        let TrieProof { root, proof, .. } = trie.prove(indices);
+        let (mut runner, addr) = setup();
+        let call = VerifyEthereumCall {
+            root: FixedBytes(root.0),
+            proof: proof.into_iter().map(Into::into).collect(),
+            keys: indices.iter().map(|index| index_key(*index).into()).collect(),
+        };
+        let result = runner.call_raw(addr, call.abi_encode());
+        let decoded = VerifyEthereumCall::abi_decode_returns(&result, true).unwrap();
+        decoded._0.into_iter().map(|value| value.value.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_transactions_trie_proofs() {
+        let mut rng = seeded_rng();
+        // past 127 the keys are two bytes long, and past 255 three
+        for small in [false, true] {
+            for count in [1, 2, 17, 128, 300] {
+                let transactions = (0..count)
+                    .map(|_| {
+                        let ty = rng.gen_range(0..=3);
+                        arb_typed_transaction(&mut rng, ty, small)
+                    })
+                    .collect::<Vec<_>>();
+                let trie = IndexTrie::new(transactions.clone());
+
+                let mut indices = (0..4).map(|_| rng.gen_range(0..count)).collect::<Vec<_>>();
+                indices.extend([0, count - 1, count, count + 200]);
+                let values = verify_index_trie(&trie, &indices);
+                for (index, value) in indices.iter().zip(values) {
+                    let expected = transactions.get(*index).cloned().unwrap_or_default();
+                    assert_eq!(value, expected, "count={count} small={small} index={index}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_withdrawals_trie_proofs() {
+        let mut rng = seeded_rng();
+        // a block holds up to 16 withdrawals; those of mainnet keep every leaf hashed, while the
+        // small indices and amounts of a devnet or local chain keep leaves short enough to
+        // be embedded
+        let mainnet = (rng.gen_range(1u64 << 24..1 << 32), 1 << 16..1 << 24, 1 << 20..1 << 40);
+        for (first, validator_indices, amounts) in [mainnet, (0, 0..128, 0..128)] {
+            let withdrawals = (0..16)
+                .map(|i| Withdrawal {
+                    index: first + i,
+                    validator_index: rng.gen_range(validator_indices.clone()),
+                    address: Address::from(rng.gen::<[u8; 20]>()),
+                    amount: rng.gen_range(amounts.clone()),
+                })
+                .collect::<Vec<_>>();
+            let trie = IndexTrie::withdrawals(&withdrawals);
+
+            let indices = (0..17).collect::<Vec<_>>();
+            let values = verify_index_trie(&trie, &indices);
+            for (withdrawal, value) in withdrawals.iter().zip(&values) {
+                assert_eq!(*value, withdrawal.encode(), "first={first}");
+            }
+            assert!(values[16].is_empty());
+        }
+    }
+
+    /// Logs emitted in the EVM, gathered into receipts and proven in the receipts trie, verify with
+    /// `VerifyEthereum` and decode back into the events that were emitted.
+    #[test]
+    fn test_receipt_log_proofs() {
+        let mut rng = seeded_rng();
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let emitter = runner.deploy(&project, "EventEmitterFixture");
+
+        let mut receipts = Vec::new();
+        // the recipient and value of each transfer, and the index of its log in the receipt
+        let mut transfers = Vec::new();
+        let mut cumulative_gas_used = 0;
+        for i in 0..40 {
+            let to = Address::from(rng.gen::<[u8; 20]>());
+            let value = U256::from(rng.gen::<u128>());
+            let (call, log_index) = match i % 3 {
+                0 => {
+                    let text = "bridge".to_string();
+                    (transferWithMessagesCall { to, value, text }.abi_encode(), 1)
+                },
+                _ => (transferCall { to, value }.abi_encode(), 0),
+            };
+            let (_, gas_used, logs) = runner.call_with_logs(emitter, call);
+            cumulative_gas_used += gas_used;
+            receipts.push(Receipt::new(rng.gen_range(0..=3), true, cumulative_gas_used, logs));
+            transfers.push((to, value, log_index));
+        }
+
+        let trie = IndexTrie::new(receipts.iter().map(Receipt::encode).collect());
+        let indices = (0..receipts.len()).collect::<Vec<_>>();
+        let values = verify_index_trie(&trie, &indices);
+        for ((value, receipt), (to, amount, log_index)) in
+            values.iter().zip(&receipts).zip(transfers)
+        {
+            assert_eq!(&Receipt::decode(value).unwrap(), receipt);
+            let transfer = decode_receipt_event::<Transfer>(value, log_index, emitter).unwrap();
+            assert_eq!((transfer.to, transfer.value), (to, amount));
+        }
+
+        let message = decode_receipt_event::<Message>(&values[0], 2, emitter).unwrap();
+        assert_eq!((message.id, message.text.as_str()), (U256::from(1), "bridge"));
+        // a log of another event, a log past the last one and a log from another emitter
+        assert!(decode_receipt_event::<Transfer>(&values[0], 0, emitter).is_err());
+        assert!(decode_receipt_event::<Transfer>(&values[0], 3, emitter).is_err());
+        assert!(decode_receipt_event::<Transfer>(&values[1], 0, Address::ZERO).is_err());
+    }
+}
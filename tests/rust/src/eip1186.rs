@@ -0,0 +1,352 @@
+//! Parsing of EIP-1186 `eth_getProof` responses into calls to `VerifyEthereumProof`, for proofs
+//! fetched from a node and saved as JSON fixtures.
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rlp::{Decodable, Encodable, Header};
+use alloy_sol_types::{sol, SolCall};
+use serde_json::Value;
+
+sol! {
+    struct StorageValue {
+        bytes key;
+        bytes value;
+    }
+
+    function VerifyEthereumProof(bytes32 root, bytes[] proof, bytes[] keys) external pure returns (StorageValue[]);
+}
+
+/// An account as stored in the state trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: B256,
+    pub code_hash: B256,
+}
+
+impl Account {
+    /// Decode the RLP list `[nonce, balance, storageRoot, codeHash]` held by the state trie.
+    pub fn decode(mut rlp: &[u8]) -> Result<Self, alloy_rlp::Error> {
+        let buf = &mut rlp;
+        let header = Header::decode(buf)?;
+        if !header.list {
+            return Err(alloy_rlp::Error::UnexpectedString)
+        }
+        if buf.len() != header.payload_length {
+            return Err(alloy_rlp::Error::UnexpectedLength)
+        }
+        let account = Self {
+            nonce: u64::decode(buf)?,
+            balance: U256::decode(buf)?,
+            storage_root: B256::decode(buf)?,
+            code_hash: B256::decode(buf)?,
+        };
+        if !buf.is_empty() {
+            return Err(alloy_rlp::Error::UnexpectedLength)
+        }
+        Ok(account)
+    }
+
+    /// The RLP encoding held by the state trie.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.nonce.encode(&mut payload);
+        self.balance.encode(&mut payload);
+        self.storage_root.encode(&mut payload);
+        self.code_hash.encode(&mut payload);
+
+        let mut out = Vec::new();
+        Header { list: true, payload_length: payload.len() }.encode(&mut out);
+        out.extend(payload);
+        out
+    }
+}
+
+/// The proof of a single key against a trie root, as `VerifyEthereumProof` takes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthereumProof {
+    pub root: B256,
+    /// Proof nodes, root first.
+    pub proof: Vec<Vec<u8>>,
+    /// The trie key, the hash of the address or storage slot.
+    pub key: Vec<u8>,
+}
+
+impl EthereumProof {
+    /// Calldata calling `VerifyEthereumProof` on the deployed `MerklePatricia` library.
+    pub fn calldata(&self) -> Vec<u8> {
+        VerifyEthereumProofCall {
+            root: self.root,
+            proof: self.proof.iter().cloned().map(Into::into).collect(),
+            keys: vec![self.key.clone().into()],
+        }
+        .abi_encode()
+    }
+}
+
+/// The proof of a storage slot against the account's storage root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageProof {
+    pub slot: B256,
+    /// The value of the slot, zero for slots that are absent from the storage trie.
+    pub value: U256,
+    pub proof: EthereumProof,
+}
+
+/// An `eth_getProof` response: an account and the proofs of some of its storage slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetProofResponse {
+    pub address: Address,
+    pub account: Account,
+    /// The proof of the account against the state root of the block it was fetched at.
+    pub account_proof: EthereumProof,
+    pub storage_proofs: Vec<StorageProof>,
+}
+
+impl GetProofResponse {
+    /// Parse a response, bare or wrapped in its JSON-RPC envelope. Responses don't include the
+    /// state root of their block, so it is taken from the block header separately.
+    pub fn parse(json: &str, state_root: B256) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(json).map_err(|e| format!("invalid json: {e}"))?;
+        let response = json.get("result").unwrap_or(&json);
+
+        let address = Address::from_slice(&fixed_bytes(field(response, "address")?, 20)?);
+        let account = Account {
+            nonce: quantity(field(response, "nonce")?)?
+                .try_into()
+                .map_err(|_| "nonce does not fit in 64 bits".to_string())?,
+            balance: quantity(field(response, "balance")?)?,
+            storage_root: B256::from_slice(&fixed_bytes(field(response, "storageHash")?, 32)?),
+            code_hash: B256::from_slice(&fixed_bytes(field(response, "codeHash")?, 32)?),
+        };
+        let account_proof = EthereumProof {
+            root: state_root,
+            proof: proof_nodes(field(response, "accountProof")?)?,
+            key: keccak256(address).to_vec(),
+        };
+
+        let storage_proofs = array(field(response, "storageProof")?)?
+            .iter()
+            .map(|storage| {
+                let slot = B256::from(quantity(field(storage, "key")?)?.to_be_bytes::<32>());
+                Ok(StorageProof {
+                    slot,
+                    value: quantity(field(storage, "value")?)?,
+                    proof: EthereumProof {
+                        root: account.storage_root,
+                        proof: proof_nodes(field(storage, "proof")?)?,
+                        key: keccak256(slot).to_vec(),
+                    },
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { address, account, account_proof, storage_proofs })
+    }
+}
+
+pub(crate) fn field<'a>(object: &'a Value, name: &str) -> Result<&'a Value, String> {
+    object.get(name).ok_or_else(|| format!("missing field {name}"))
+}
+
+pub(crate) fn array(value: &Value) -> Result<&Vec<Value>, String> {
+    value.as_array().ok_or_else(|| format!("expected an array, got {value}"))
+}
+
+pub(crate) fn hex_str(value: &Value) -> Result<&str, String> {
+    let hex = value.as_str().ok_or_else(|| format!("expected a hex string, got {value}"))?;
+    hex.strip_prefix("0x")
+        .ok_or_else(|| format!("hex string without 0x prefix: {hex}"))
+}
+
+/// Unformatted data: an even number of hex digits.
+pub(crate) fn bytes(value: &Value) -> Result<Vec<u8>, String> {
+    hex::decode(hex_str(value)?).map_err(|e| format!("invalid hex data {value}: {e}"))
+}
+
+pub(crate) fn fixed_bytes(value: &Value, len: usize) -> Result<Vec<u8>, String> {
+    let bytes = bytes(value)?;
+    if bytes.len() != len {
+        return Err(format!("expected {len} bytes, got {value}"))
+    }
+    Ok(bytes)
+}
+
+/// A quantity: a number in hex, without leading zeroes though these are tolerated.
+pub(crate) fn quantity(value: &Value) -> Result<U256, String> {
+    U256::from_str_radix(hex_str(value)?, 16).map_err(|e| format!("invalid quantity {value}: {e}"))
+}
+
+fn proof_nodes(value: &Value) -> Result<Vec<Vec<u8>>, String> {
+    array(value)?.iter().map(bytes).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        ethereum_trie::EthereumLayout,
+        evm_runner::{project_root, EvmRunner},
+        seeded_rng,
+        storage_layout::decode_storage_value,
+        trie_utils::generate_trie_proof,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// An `eth_getProof` response, in its JSON-RPC envelope, for an account with a few storage
+    /// slots and the state root it is proven against. Slot 100 is absent from the account's
+    /// storage.
+    fn eip1186_response(rng: &mut impl Rng) -> (B256, serde_json::Value) {
+        let slots = (0u64..8)
+            .map(|slot| (U256::from(slot), U256::from(rng.gen::<u128>()) << rng.gen_range(0..128)))
+            .collect::<Vec<_>>();
+        let slot_key = |slot: U256| keccak256(slot.to_be_bytes::<32>()).to_vec();
+        let entries = slots
+            .iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(slot, value)| (slot_key(*slot), alloy_rlp::encode(value)))
+            .collect::<Vec<_>>();
+        let proven = [0, 1, 2, 3, 100].map(U256::from);
+        let storage = generate_trie_proof::<EthereumLayout>(
+            &entries,
+            &proven.iter().map(|slot| slot_key(*slot)).collect::<Vec<_>>(),
+        );
+
+        let address = Address::from(rng.gen::<[u8; 20]>());
+        let account = Account {
+            nonce: rng.gen(),
+            balance: U256::from(rng.gen::<u128>()),
+            storage_root: B256::from(storage.root.0),
+            code_hash: keccak256(rng.gen::<[u8; 32]>()),
+        };
+        let accounts = (0..16)
+            .map(|_| {
+                let account = Account {
+                    nonce: rng.gen(),
+                    balance: U256::from(rng.gen::<u64>()),
+                    storage_root: B256::from(rng.gen::<[u8; 32]>()),
+                    code_hash: B256::from(rng.gen::<[u8; 32]>()),
+                };
+                (rng.gen::<[u8; 32]>().to_vec(), account.encode())
+            })
+            .chain([(keccak256(address).to_vec(), account.encode())])
+            .collect::<Vec<_>>();
+        let state =
+            generate_trie_proof::<EthereumLayout>(&accounts, &[keccak256(address).to_vec()]);
+
+        let nodes = |proof: &[Vec<u8>]| {
+            proof.iter().map(|node| format!("0x{}", hex::encode(node))).collect::<Vec<_>>()
+        };
+        let storage_proof = proven
+            .iter()
+            .map(|slot| {
+                let value = slots.iter().find(|(s, _)| s == slot).map_or(U256::ZERO, |(_, v)| *v);
+                serde_json::json!({
+                    "key": format!("{slot:#x}"),
+                    "value": format!("{value:#x}"),
+                    "proof": nodes(&storage.proof),
+                })
+            })
+            .collect::<Vec<_>>();
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "address": address.to_string(),
+                "balance": format!("{:#x}", account.balance),
+                "codeHash": account.code_hash.to_string(),
+                "nonce": format!("{:#x}", account.nonce),
+                "storageHash": account.storage_root.to_string(),
+                "accountProof": nodes(&state.proof),
+                "storageProof": storage_proof,
+            },
+        });
+
+        (B256::from(state.root.0), response)
+    }
+
+    /// Verify the proofs of a parsed `eth_getProof` response with the `MerklePatricia` library,
+    /// returning the proven account, if it exists, and the value of each proven storage slot.
+    pub(crate) fn verify_get_proof_response(
+        parsed: &GetProofResponse,
+    ) -> (Option<Account>, Vec<U256>) {
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let library = runner.deploy(&project, "MerklePatricia");
+
+        let result = runner.call_raw(library, parsed.account_proof.calldata());
+        let values = VerifyEthereumProofCall::abi_decode_returns(&result, true).unwrap()._0;
+        let account = match &values[0].value[..] {
+            [] => None,
+            rlp => Some(Account::decode(rlp).unwrap()),
+        };
+
+        let storage = parsed
+            .storage_proofs
+            .iter()
+            .map(|storage| {
+                let result = runner.call_raw(library, storage.proof.calldata());
+                let values = VerifyEthereumProofCall::abi_decode_returns(&result, true).unwrap()._0;
+                decode_storage_value(&values[0].value).unwrap()
+            })
+            .collect();
+
+        (account, storage)
+    }
+
+    /// The calls built from an `eth_getProof` response verify against the `MerklePatricia` library,
+    /// proving the account and the value of each storage slot.
+    #[test]
+    fn test_eip1186_get_proof_response() {
+        let mut rng = seeded_rng();
+        let (state_root, response) = eip1186_response(&mut rng);
+        let parsed = GetProofResponse::parse(&response.to_string(), state_root).unwrap();
+        assert_eq!(parsed.storage_proofs.len(), 5);
+
+        let (account, storage) = verify_get_proof_response(&parsed);
+        assert_eq!(account, Some(parsed.account));
+        assert_eq!(storage, parsed.storage_proofs.iter().map(|s| s.value).collect::<Vec<_>>());
+    }
+
+    /// Nodes echo storage keys back as they were requested, so a key may come as 32 bytes of data
+    /// with leading zeroes rather than as a quantity. Both name the same slot.
+    #[test]
+    fn test_eip1186_zero_padded_storage_keys() {
+        let mut rng = seeded_rng();
+        let (state_root, response) = eip1186_response(&mut rng);
+        let parsed = GetProofResponse::parse(&response.to_string(), state_root).unwrap();
+
+        let mut padded = response.clone();
+        for storage in padded.pointer_mut("/result/storageProof").unwrap().as_array_mut().unwrap() {
+            let slot = U256::from_str_radix(&storage["key"].as_str().unwrap()[2..], 16).unwrap();
+            storage["key"] = B256::from(slot.to_be_bytes::<32>()).to_string().into();
+        }
+        assert_eq!(padded["result"]["storageProof"][1]["key"], format!("0x{:064x}", 1));
+
+        let padded = GetProofResponse::parse(&padded.to_string(), state_root).unwrap();
+        assert_eq!(padded, parsed);
+        let (_, storage) = verify_get_proof_response(&padded);
+        assert_eq!(storage, parsed.storage_proofs.iter().map(|s| s.value).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_eip1186_rejects_malformed_responses() {
+        let (state_root, response) = eip1186_response(&mut StdRng::seed_from_u64(0));
+        let cases = [
+            ("/result/address", serde_json::json!("0x1234")),
+            ("/result/nonce", serde_json::json!("0x10000000000000000")),
+            ("/result/balance", serde_json::json!("12")),
+            ("/result/storageHash", serde_json::json!(null)),
+            ("/result/accountProof/0", serde_json::json!("0xabc")),
+            ("/result/storageProof/0/key", serde_json::json!("0xzz")),
+            ("/result/storageProof", serde_json::json!({})),
+        ];
+
+        for (pointer, replacement) in cases {
+            let mut response = response.clone();
+            *response.pointer_mut(pointer).unwrap() = replacement;
+            let parsed = GetProofResponse::parse(&response.to_string(), state_root);
+            assert!(parsed.is_err(), "{pointer}");
+        }
+    }
+}
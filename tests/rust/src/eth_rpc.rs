@@ -0,0 +1,585 @@
+//! A local stand-in for an Ethereum node, serving `eth_getProof`, `eth_getBlockByNumber` and
+//! `eth_getStorageAt` over JSON-RPC from an in-memory state, so that the EIP-1186 path can be
+//! tested end to end without a network.
+
+use crate::{
+    eip1186::{self, Account},
+    ethereum_trie::EthereumLayout,
+    trie_utils::generate_trie_proof,
+};
+use alloy_primitives::{keccak256, Address, B256, U256};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// An account of the in-memory state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenesisAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Vec<u8>,
+    /// Non-zero storage slots.
+    pub storage: BTreeMap<B256, U256>,
+}
+
+/// The state of the single block served by [`RpcServer`], with its trie roots computed once as it
+/// is built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    accounts: BTreeMap<Address, GenesisAccount>,
+    storage_roots: BTreeMap<Address, B256>,
+    state_root: B256,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new(BTreeMap::new())
+    }
+}
+
+impl State {
+    /// Build the state from its accounts, computing the storage root of each and the state root.
+    pub fn new(accounts: BTreeMap<Address, GenesisAccount>) -> Self {
+        let storage_roots = accounts
+            .iter()
+            .map(|(address, account)| (*address, Self::storage_root(account)))
+            .collect();
+        let mut state = Self { accounts, storage_roots, state_root: B256::ZERO };
+        state.state_root = trie_root(&state.state_entries());
+        state
+    }
+
+    /// Build the state from the `alloc` of a genesis file, keyed by address, with each account's
+    /// optional `nonce`, `balance`, `code` and `storage`. A genesis without `alloc` is read as the
+    /// alloc itself. As geth does, addresses and slots may leave out their `0x` prefix, and the
+    /// nonce and balance may be given in decimal.
+    pub fn from_genesis(json: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(json).map_err(|e| format!("invalid json: {e}"))?;
+        let alloc = json.get("alloc").unwrap_or(&json);
+        let alloc = alloc.as_object().ok_or_else(|| format!("expected an object, got {alloc}"))?;
+
+        let mut accounts = BTreeMap::new();
+        for (address, account) in alloc {
+            let address = address_param(&prefixed(address))?;
+            let optional = |name: &str| account.get(name).filter(|value| !value.is_null());
+            let nonce = match optional("nonce") {
+                Some(nonce) => hex_or_decimal(nonce)?
+                    .try_into()
+                    .map_err(|_| format!("nonce of {address} does not fit in 64 bits"))?,
+                None => 0,
+            };
+            let balance = optional("balance").map(hex_or_decimal).transpose()?;
+            let code = optional("code").map(eip1186::bytes).transpose()?;
+
+            let mut storage = BTreeMap::new();
+            if let Some(slots) = optional("storage") {
+                let slots =
+                    slots.as_object().ok_or_else(|| format!("expected an object, got {slots}"))?;
+                for (slot, value) in slots {
+                    let slot = slot_param(&prefixed(slot))?;
+                    let value = eip1186::quantity(value)?;
+                    if !value.is_zero() {
+                        storage.insert(slot, value);
+                    }
+                }
+            }
+
+            let account = GenesisAccount {
+                nonce,
+                balance: balance.unwrap_or_default(),
+                code: code.unwrap_or_default(),
+                storage,
+            };
+            accounts.insert(address, account);
+        }
+        Ok(Self::new(accounts))
+    }
+
+    pub fn accounts(&self) -> &BTreeMap<Address, GenesisAccount> {
+        &self.accounts
+    }
+
+    fn storage_root(account: &GenesisAccount) -> B256 {
+        trie_root(&Self::storage_entries(account))
+    }
+
+    fn storage_entries(account: &GenesisAccount) -> Vec<(Vec<u8>, Vec<u8>)> {
+        account
+            .storage
+            .iter()
+            .map(|(slot, value)| (keccak256(slot).to_vec(), alloy_rlp::encode(value)))
+            .collect()
+    }
+
+    fn state_entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.accounts
+            .iter()
+            .map(|(address, _)| (keccak256(address).to_vec(), self.account(address).encode()))
+            .collect()
+    }
+
+    /// The account at `address` as held by the state trie, empty if there is none.
+    pub fn account(&self, address: &Address) -> Account {
+        let empty = GenesisAccount::default();
+        let account = self.accounts.get(address).unwrap_or(&empty);
+        let storage_root = match self.storage_roots.get(address) {
+            Some(root) => *root,
+            None => Self::storage_root(&empty),
+        };
+        Account {
+            nonce: account.nonce,
+            balance: account.balance,
+            storage_root,
+            code_hash: keccak256(&account.code),
+        }
+    }
+
+    pub fn state_root(&self) -> B256 {
+        self.state_root
+    }
+
+    /// The value of `slot` in the storage of `address`, zero if either is absent.
+    pub fn storage_at(&self, address: &Address, slot: &B256) -> U256 {
+        self.accounts
+            .get(address)
+            .and_then(|account| account.storage.get(slot))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The EIP-1186 proof of the account at `address` and of `slots` in its storage.
+    pub fn get_proof(&self, address: &Address, slots: &[B256]) -> Value {
+        let nodes = |proof: Vec<Vec<u8>>| {
+            proof.iter().map(|node| format!("0x{}", hex::encode(node))).collect::<Vec<_>>()
+        };
+        let account = self.account(address);
+        let account_proof = generate_trie_proof::<EthereumLayout>(
+            &self.state_entries(),
+            &[keccak256(address).to_vec()],
+        );
+
+        let storage = self.accounts.get(address).map(Self::storage_entries).unwrap_or_default();
+        let storage_proof = slots
+            .iter()
+            .map(|slot| {
+                let proof =
+                    generate_trie_proof::<EthereumLayout>(&storage, &[keccak256(slot).to_vec()]);
+                json!({
+                    "key": slot.to_string(),
+                    "value": format!("{:#x}", self.storage_at(address, slot)),
+                    "proof": nodes(proof.proof),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "address": address.to_string(),
+            "balance": format!("{:#x}", account.balance),
+            "codeHash": account.code_hash.to_string(),
+            "nonce": format!("{:#x}", account.nonce),
+            "storageHash": account.storage_root.to_string(),
+            "accountProof": nodes(account_proof.proof),
+            "storageProof": storage_proof,
+        })
+    }
+}
+
+/// Genesis hex data, which may leave out its `0x` prefix, as hex data with the prefix.
+fn prefixed(hex: &str) -> Value {
+    json!(format!("0x{}", hex.strip_prefix("0x").unwrap_or(hex)))
+}
+
+/// A genesis number: hex with a `0x` prefix, decimal without one, or a JSON number.
+fn hex_or_decimal(value: &Value) -> Result<U256, String> {
+    if let Some(number) = value.as_u64() {
+        return Ok(U256::from(number))
+    }
+    let number = value.as_str().ok_or_else(|| format!("expected a number, got {value}"))?;
+    match number.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16),
+        None => U256::from_str_radix(number, 10),
+    }
+    .map_err(|e| format!("invalid number {value}: {e}"))
+}
+
+fn trie_root(entries: &[(Vec<u8>, Vec<u8>)]) -> B256 {
+    B256::from(generate_trie_proof::<EthereumLayout>(entries, &[]).root.0)
+}
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// The longest request body served, far more than any single call needs.
+const MAX_BODY_LEN: usize = 1 << 20;
+/// The longest header line read.
+const MAX_HEADER_LEN: u64 = 8 << 10;
+/// How long a connection may stall. Connections are served one at a time, so a client that stops
+/// mid request would otherwise hold up every later one, and the server's shutdown.
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves JSON-RPC over HTTP on a local port from a [`State`], as the single block `0x0`, until
+/// dropped. Each connection carries a single request.
+pub struct RpcServer {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RpcServer {
+    pub fn spawn(state: State) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::spawn({
+            let shutdown = shutdown.clone();
+            move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break
+                    }
+                    // a client hanging up mid request only fails its own connection
+                    if let Ok(stream) = stream {
+                        let _ = serve_connection(&state, stream);
+                    }
+                }
+            }
+        });
+        Ok(Self { addr, shutdown, handle: Some(handle) })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for RpcServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up the listener so that it sees the shutdown flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve_connection(state: &State, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if (&mut reader).take(MAX_HEADER_LEN).read_line(&mut line)? == 0 {
+            return Ok(())
+        }
+        if !line.ends_with('\n') {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, "header line too long"))
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    if content_length > MAX_BODY_LEN {
+        return write!(
+            &stream,
+            "HTTP/1.1 413 Payload Too Large\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(request) => handle_request(state, &request),
+        Err(e) => error_response(&Value::Null, PARSE_ERROR, format!("invalid json: {e}")),
+    }
+    .to_string();
+    write!(
+        &stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{response}",
+        response.len()
+    )
+}
+
+fn error_response(id: &Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answer a JSON-RPC request, with an error response for unknown methods or invalid params.
+pub fn handle_request(state: &State, request: &Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+    let params = request.get("params").and_then(Value::as_array).cloned().unwrap_or_default();
+
+    let result = match method {
+        "eth_getProof" => get_proof(state, &params),
+        "eth_getBlockByNumber" => get_block_by_number(state, &params),
+        "eth_getStorageAt" => get_storage_at(state, &params),
+        _ => return error_response(&id, METHOD_NOT_FOUND, format!("method {method} not found")),
+    };
+
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => error_response(&id, INVALID_PARAMS, message),
+    }
+}
+
+fn param(params: &[Value], i: usize) -> &Value {
+    params.get(i).unwrap_or(&Value::Null)
+}
+
+fn address_param(value: &Value) -> Result<Address, String> {
+    Ok(Address::from_slice(&eip1186::fixed_bytes(value, 20)?))
+}
+
+fn slot_param(value: &Value) -> Result<B256, String> {
+    Ok(B256::from(eip1186::quantity(value)?.to_be_bytes::<32>()))
+}
+
+/// `eth_getProof(address, slots, block)`
+fn get_proof(state: &State, params: &[Value]) -> Result<Value, String> {
+    let address = address_param(param(params, 0))?;
+    let slots = eip1186::array(param(params, 1))?
+        .iter()
+        .map(slot_param)
+        .collect::<Result<Vec<_>, _>>()?;
+    check_block(param(params, 2))?;
+    Ok(state.get_proof(&address, &slots))
+}
+
+/// `eth_getBlockByNumber(block, full_transactions)`, of which only the state root is served.
+fn get_block_by_number(state: &State, params: &[Value]) -> Result<Value, String> {
+    check_block(param(params, 0))?;
+    Ok(json!({ "number": "0x0", "stateRoot": state.state_root().to_string() }))
+}
+
+/// `eth_getStorageAt(address, slot, block)`
+fn get_storage_at(state: &State, params: &[Value]) -> Result<Value, String> {
+    let address = address_param(param(params, 0))?;
+    let slot = slot_param(param(params, 1))?;
+    check_block(param(params, 2))?;
+    let value = B256::from(state.storage_at(&address, &slot).to_be_bytes::<32>());
+    Ok(json!(value.to_string()))
+}
+
+/// The state is the only block, so it is the answer to any tag and to block number zero.
+fn check_block(block: &Value) -> Result<(), String> {
+    match block.as_str() {
+        Some("latest" | "earliest" | "pending" | "safe" | "finalized") => Ok(()),
+        Some(_) if eip1186::quantity(block)?.is_zero() => Ok(()),
+        _ => Err(format!("unknown block {block}")),
+    }
+}
+
+/// Send a JSON-RPC request to `url` over HTTP and return its result.
+pub fn rpc_call(url: &str, method: &str, params: Value) -> Result<Value, String> {
+    let host = url.strip_prefix("http://").ok_or_else(|| format!("not an http url: {url}"))?;
+    let host = host.trim_end_matches('/');
+    let mut stream = TcpStream::connect(host).map_err(|e| format!("connecting to {url}: {e}"))?;
+    stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(IO_TIMEOUT)))
+        .map_err(|e| format!("setting timeouts: {e}"))?;
+
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+    write!(
+        stream,
+        "POST / HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .map_err(|e| format!("sending request: {e}"))?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| format!("reading response: {e}"))?;
+    let (_, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| format!("malformed response: {response}"))?;
+    let mut response: Value =
+        serde_json::from_str(body).map_err(|e| format!("invalid json response: {e}"))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(format!("{method} failed: {error}"))
+    }
+    response
+        .get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| format!("response without a result: {response}"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        eip1186::{tests::verify_get_proof_response, GetProofResponse},
+        seeded_rng,
+    };
+    use hex_literal::hex;
+    use rand::Rng;
+    use std::time::Instant;
+
+    /// A genesis alloc with a contract holding a few storage slots among externally owned accounts.
+    pub(crate) fn rpc_genesis(rng: &mut impl Rng, contract: Address) -> serde_json::Value {
+        let mut alloc = serde_json::Map::new();
+        for _ in 0..24 {
+            let address = Address::from(rng.gen::<[u8; 20]>());
+            let account = serde_json::json!({
+                "balance": format!("{:#x}", U256::from(rng.gen::<u128>())),
+                "nonce": format!("{:#x}", rng.gen::<u32>()),
+            });
+            alloc.insert(address.to_string(), account);
+        }
+        let storage = (0u64..32)
+            .map(|slot| {
+                let value = U256::from(rng.gen::<u128>()) >> rng.gen_range(0..128);
+                (
+                    B256::from(U256::from(slot).to_be_bytes::<32>()).to_string(),
+                    format!("{value:#x}"),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let account = serde_json::json!({
+            "balance": "0x0",
+            "code": format!("0x{}", hex::encode(rng.gen::<[u8; 32]>())),
+            "storage": storage,
+        });
+        alloc.insert(contract.to_string(), account);
+        serde_json::json!({ "alloc": alloc })
+    }
+
+    /// A genesis as geth writes it, with unprefixed addresses and slots and decimal numbers, loads
+    /// the same state as one with prefixed addresses and slots and hex numbers.
+    #[test]
+    fn test_eth_rpc_state_from_geth_genesis() {
+        let geth = r#"{"alloc": {"7df9a875a174b3bc565e6424a0050ebc1b2d1d82": {
+            "balance": "1000000000000000000",
+            "nonce": "2",
+            "storage": {"01": "0x05"}
+        }}}"#;
+        let prefixed = r#"{"alloc": {"0x7df9a875a174b3bc565e6424a0050ebc1b2d1d82": {
+            "balance": "0xde0b6b3a7640000",
+            "nonce": "0x2",
+            "storage": {"0x01": "0x05"}
+        }}}"#;
+        let state = State::from_genesis(geth).unwrap();
+        assert_eq!(state.accounts(), State::from_genesis(prefixed).unwrap().accounts());
+
+        let address = Address::from(hex!("7df9a875a174b3bc565e6424a0050ebc1b2d1d82"));
+        let account = &state.accounts()[&address];
+        assert_eq!(account.balance, U256::from(10u64.pow(18)));
+        assert_eq!(account.nonce, 2);
+        assert_eq!(account.storage[&B256::with_last_byte(1)], U256::from(5));
+    }
+
+    /// Proofs fetched from the local node verify against the state root of its block, and agree
+    /// with `eth_getStorageAt`, for present and absent slots and for an absent account.
+    #[test]
+    fn test_eth_rpc_get_proof() {
+        let mut rng = seeded_rng();
+        let contract = Address::from(rng.gen::<[u8; 20]>());
+        let state = State::from_genesis(&rpc_genesis(&mut rng, contract).to_string()).unwrap();
+        let server = RpcServer::spawn(state).unwrap();
+        let url = server.url();
+
+        let block =
+            rpc_call(&url, "eth_getBlockByNumber", serde_json::json!(["latest", false])).unwrap();
+        let state_root = block["stateRoot"].as_str().unwrap().parse::<B256>().unwrap();
+
+        let slots = ["0x0", "0x1", "0x1f", "0x20", "0xdead"];
+        let response = rpc_call(
+            &url,
+            "eth_getProof",
+            serde_json::json!([contract.to_string(), slots, "latest"]),
+        );
+        let parsed = GetProofResponse::parse(&response.unwrap().to_string(), state_root).unwrap();
+        let (account, storage) = verify_get_proof_response(&parsed);
+        assert_eq!(account, Some(parsed.account.clone()));
+        assert_eq!(storage, parsed.storage_proofs.iter().map(|s| s.value).collect::<Vec<_>>());
+        assert_eq!(&storage[3..], [U256::ZERO, U256::ZERO]);
+
+        for (slot, value) in slots.iter().zip(&storage) {
+            let params = serde_json::json!([contract.to_string(), slot, "0x0"]);
+            let stored = rpc_call(&url, "eth_getStorageAt", params).unwrap();
+            assert_eq!(
+                stored.as_str().unwrap().parse::<B256>().unwrap(),
+                B256::from(value.to_be_bytes::<32>())
+            );
+        }
+
+        let absent = Address::from(rng.gen::<[u8; 20]>());
+        let response = rpc_call(
+            &url,
+            "eth_getProof",
+            serde_json::json!([absent.to_string(), slots, "latest"]),
+        );
+        let parsed = GetProofResponse::parse(&response.unwrap().to_string(), state_root).unwrap();
+        assert_eq!(verify_get_proof_response(&parsed).0, None);
+    }
+
+    #[test]
+    fn test_eth_rpc_errors() {
+        let server = RpcServer::spawn(State::default()).unwrap();
+        let url = server.url();
+
+        assert!(rpc_call(&url, "eth_chainId", serde_json::json!([])).is_err());
+        assert!(rpc_call(&url, "eth_getBlockByNumber", serde_json::json!(["0x1", false])).is_err());
+        assert!(
+            rpc_call(&url, "eth_getProof", serde_json::json!(["0x1234", [], "latest"])).is_err()
+        );
+        assert!(rpc_call(&url, "eth_getStorageAt", serde_json::json!([Address::ZERO.to_string()]))
+            .is_err());
+
+        // the empty state has the root of the empty trie
+        let block =
+            rpc_call(&url, "eth_getBlockByNumber", serde_json::json!(["0x0", false])).unwrap();
+        assert_eq!(
+            block["stateRoot"],
+            "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+        );
+    }
+
+    /// A client that stalls mid request holds up later requests and the server's shutdown only
+    /// until its connection times out, and requests too large to serve are refused.
+    #[test]
+    fn test_eth_rpc_stalled_and_oversized_requests() {
+        let server = RpcServer::spawn(State::default()).unwrap();
+        let url = server.url();
+        let host = url.strip_prefix("http://").unwrap();
+
+        let mut stalled = TcpStream::connect(host).unwrap();
+        write!(stalled, "POST / HTTP/1.1\r\nContent-Length: 100\r\n\r\n{{").unwrap();
+        assert!(rpc_call(&url, "eth_getBlockByNumber", serde_json::json!(["0x0", false])).is_ok());
+
+        let mut oversized = TcpStream::connect(host).unwrap();
+        write!(oversized, "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", 1u64 << 40).unwrap();
+        let mut response = String::new();
+        oversized.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+
+        let _stalled = TcpStream::connect(host).unwrap();
+        let start = Instant::now();
+        drop(server);
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,182 @@
+//! Verification of a storage slot together with the account holding it, with
+//! `VerifyEthereumStorageProof` run in the EVM.
+
+use crate::{
+    eip1186::Account,
+    evm_runner::{project_root, EvmRunner},
+};
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::sol;
+
+sol! {
+    struct EthereumAccount {
+        uint256 nonce;
+        uint256 balance;
+        bytes32 storageRoot;
+        bytes32 codeHash;
+    }
+
+    function VerifyEthereumStorageProof(bytes32 stateRoot, address account, bytes[] accountProof, bytes32 slot, bytes[] storageProof) external pure returns (EthereumAccount, uint256);
+}
+
+/// A storage slot verified together with the account holding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedStorage {
+    /// The account, `None` if it is absent from the state trie.
+    pub account: Option<Account>,
+    /// The value of the slot, zero if it is absent from the account's storage.
+    pub value: U256,
+}
+
+/// Verify `slot` in the storage of the account at `address` with `VerifyEthereumStorageProof`,
+/// which proves the account against `state_root` and the slot against the account's storage root,
+/// failing with the revert reason if either proof is invalid.
+pub fn verify_ethereum_storage(
+    state_root: B256,
+    address: Address,
+    account_proof: &[Vec<u8>],
+    slot: B256,
+    storage_proof: &[Vec<u8>],
+) -> Result<VerifiedStorage, String> {
+    let mut runner = EvmRunner::new();
+    let library = runner.deploy(&project_root(), "MerklePatricia");
+    let call = VerifyEthereumStorageProofCall {
+        stateRoot: state_root,
+        account: address,
+        accountProof: account_proof.iter().cloned().map(Into::into).collect(),
+        slot,
+        storageProof: storage_proof.iter().cloned().map(Into::into).collect(),
+    };
+    let decoded = runner.call_sol(library, call)?;
+
+    // absent accounts come back zeroed, while every account in the trie has a code hash
+    let account = (decoded._0.codeHash != B256::ZERO)
+        .then(|| {
+            Ok::<_, String>(Account {
+                nonce: decoded._0.nonce.try_into().map_err(|_| "nonce overflows 64 bits")?,
+                balance: decoded._0.balance,
+                storage_root: decoded._0.storageRoot,
+                code_hash: decoded._0.codeHash,
+            })
+        })
+        .transpose()?;
+
+    Ok(VerifiedStorage { account, value: decoded._1 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eip1186::{GetProofResponse, VerifyEthereumProofCall},
+        eth_rpc::{self, tests::rpc_genesis},
+        seeded_rng,
+        storage_layout::decode_storage_value,
+    };
+    use alloy_sol_types::SolCall;
+    use rand::Rng;
+
+    /// `verify_ethereum_storage` proves a slot together with its account, for present and absent
+    /// slots, an account without storage and an absent account, and rejects a corrupted proof.
+    #[test]
+    fn test_verify_ethereum_storage() {
+        let mut rng = seeded_rng();
+        let contract = Address::from(rng.gen::<[u8; 20]>());
+        let state =
+            eth_rpc::State::from_genesis(&rpc_genesis(&mut rng, contract).to_string()).unwrap();
+        let state_root = state.state_root();
+        let eoa = *state.accounts().keys().find(|address| **address != contract).unwrap();
+        let absent = Address::from(rng.gen::<[u8; 20]>());
+
+        let slots = [0u64, 1, 31, 32].map(|slot| B256::from(U256::from(slot).to_be_bytes::<32>()));
+        for address in [contract, eoa, absent] {
+            let response = state.get_proof(&address, &slots);
+            let parsed = GetProofResponse::parse(&response.to_string(), state_root).unwrap();
+            for (slot, storage) in slots.iter().zip(&parsed.storage_proofs) {
+                let verified = verify_ethereum_storage(
+                    state_root,
+                    address,
+                    &parsed.account_proof.proof,
+                    *slot,
+                    &storage.proof.proof,
+                )
+                .unwrap();
+                let expected =
+                    state.accounts().contains_key(&address).then(|| state.account(&address));
+                assert_eq!(verified.account, expected, "address={address}");
+                assert_eq!(verified.value, state.storage_at(&address, slot), "slot={slot}");
+            }
+        }
+
+        let slot = *state.accounts()[&contract].storage.keys().next().unwrap();
+        let parsed =
+            GetProofResponse::parse(&state.get_proof(&contract, &[slot]).to_string(), state_root)
+                .unwrap();
+        let mut storage_proof = parsed.storage_proofs[0].proof.proof.clone();
+        let node = rng.gen_range(0..storage_proof.len());
+        let byte = rng.gen_range(0..storage_proof[node].len());
+        storage_proof[node][byte] ^= 1 << rng.gen_range(0..8);
+        let verified = verify_ethereum_storage(
+            state_root,
+            contract,
+            &parsed.account_proof.proof,
+            slot,
+            &storage_proof,
+        );
+        assert!(verified.is_err(), "{verified:?}");
+    }
+
+    /// Gas of proving a slot with `VerifyEthereumStorageProof`, against proving the account and the
+    /// slot with two `VerifyEthereumProof` calls and decoding the account off chain. Each figure
+    /// includes the intrinsic cost of its transaction. Run with `--ignored --nocapture` to print
+    /// the measurements.
+    #[test]
+    #[ignore]
+    fn test_verify_ethereum_storage_gas() {
+        let mut rng = seeded_rng();
+        let contract = Address::from(rng.gen::<[u8; 20]>());
+        let state =
+            eth_rpc::State::from_genesis(&rpc_genesis(&mut rng, contract).to_string()).unwrap();
+        let state_root = state.state_root();
+        let slot = *state.accounts()[&contract].storage.keys().next().unwrap();
+        let parsed =
+            GetProofResponse::parse(&state.get_proof(&contract, &[slot]).to_string(), state_root)
+                .unwrap();
+
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let library = runner.deploy(&project, "MerklePatricia");
+
+        let call = VerifyEthereumStorageProofCall {
+            stateRoot: state_root,
+            account: contract,
+            accountProof: parsed.account_proof.proof.iter().cloned().map(Into::into).collect(),
+            slot,
+            storageProof: parsed.storage_proofs[0]
+                .proof
+                .proof
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect(),
+        };
+        let (result, combined) = runner.call_with_gas(library, call.abi_encode());
+        let decoded = VerifyEthereumStorageProofCall::abi_decode_returns(&result, true).unwrap();
+        assert_eq!(decoded._1, state.storage_at(&contract, &slot));
+
+        let (result, account) = runner.call_with_gas(library, parsed.account_proof.calldata());
+        let values = VerifyEthereumProofCall::abi_decode_returns(&result, true).unwrap()._0;
+        let decoded_account = Account::decode(&values[0].value).unwrap();
+        assert_eq!(decoded_account.storage_root, decoded._0.storageRoot);
+        let (result, storage) =
+            runner.call_with_gas(library, parsed.storage_proofs[0].proof.calldata());
+        let values = VerifyEthereumProofCall::abi_decode_returns(&result, true).unwrap()._0;
+        assert_eq!(decode_storage_value(&values[0].value).unwrap(), decoded._1);
+        println!(
+            "account_proof={:>3}  storage_proof={:>3}  combined_gas={combined:>8}  \
+             account_gas={account:>8}  storage_gas={storage:>8}",
+            parsed.account_proof.proof.len(),
+            parsed.storage_proofs[0].proof.proof.len(),
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! A trie-db layout for Ethereum's Merkle Patricia trie, so that proofs for the Ethereum verifier
+//! can be generated from arbitrary data with `TrieDBMutBuilder` and `Recorder`.
+
+use alloy_rlp::{Encodable, Error, Header};
+use sp_core::KeccakHasher;
+use std::{borrow::Borrow, marker::PhantomData, ops::Range};
+use trie_db::{
+    nibble_ops,
+    node::{NibbleSlicePlan, NodeHandlePlan, NodePlan, Value, ValuePlan},
+    ChildReference, Hasher, NodeCodec, TrieLayout,
+};
+
+/// The RLP encoding of the empty string, which stands for the empty trie, an empty branch slot and
+/// a branch without a value.
+const EMPTY_STRING: u8 = 0x80;
+
+/// Hex prefix flag of a leaf's partial key.
+const LEAF_FLAG: u8 = 0x20;
+
+/// Hex prefix flag of a partial key with an odd number of nibbles.
+const ODD_FLAG: u8 = 0x10;
+
+/// Ethereum's trie: keccak hashing, extension nodes and values that are always stored inline.
+pub struct EthereumLayout;
+
+impl TrieLayout for EthereumLayout {
+    const USE_EXTENSION: bool = true;
+    const ALLOW_EMPTY: bool = false;
+    const MAX_INLINE_VALUE: Option<u32> = None;
+    type Hash = KeccakHasher;
+    type Codec = RlpNodeCodec<KeccakHasher>;
+}
+
+/// Encodes nodes as RLP lists as described in the yellow paper: leaves and extensions as a hex
+/// prefix encoded partial key followed by the value or child, and branches as 16 children followed
+/// by the value. Children shorter than a hash are embedded in their parent.
+pub struct RlpNodeCodec<H>(PhantomData<H>);
+
+/// An item in the payload of an encoded node.
+struct Item {
+    list: bool,
+    /// The whole item, header included.
+    encoded: Range<usize>,
+    payload: Range<usize>,
+}
+
+/// Split the encoded list `data` into its items, requiring that nothing follows the list.
+fn decode_list(data: &[u8]) -> Result<Vec<Item>, Error> {
+    let buf = &mut &data[..];
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(Error::UnexpectedString)
+    }
+    if buf.len() != header.payload_length {
+        return Err(Error::UnexpectedLength)
+    }
+
+    let mut items = Vec::new();
+    while !buf.is_empty() {
+        let start = data.len() - buf.len();
+        let header = Header::decode(buf)?;
+        if buf.len() < header.payload_length {
+            return Err(Error::InputTooShort)
+        }
+        let payload_start = data.len() - buf.len();
+        let payload = payload_start..payload_start + header.payload_length;
+        *buf = &buf[header.payload_length..];
+        items.push(Item { list: header.list, encoded: start..payload.end, payload });
+    }
+    Ok(items)
+}
+
+/// Decode a child reference: the empty string for no child, a hash, or an embedded node.
+fn decode_child<H: Hasher>(item: &Item) -> Result<Option<NodeHandlePlan>, Error> {
+    if item.list {
+        return Ok(Some(NodeHandlePlan::Inline(item.encoded.clone())))
+    }
+    match item.payload.len() {
+        0 => Ok(None),
+        len if len == H::LENGTH => Ok(Some(NodeHandlePlan::Hash(item.payload.clone()))),
+        _ => Err(Error::Custom("child is neither a hash nor an embedded node")),
+    }
+}
+
+/// Hex prefix encode a partial key of `number_nibble` nibbles. For an odd number of nibbles the
+/// first byte of `partial` holds a single nibble, which shares a byte with the flags.
+fn encode_partial(
+    mut partial: impl Iterator<Item = u8>,
+    number_nibble: usize,
+    leaf: bool,
+) -> Vec<u8> {
+    let flags = if leaf { LEAF_FLAG } else { 0 };
+    let first = match number_nibble % 2 {
+        1 => flags | ODD_FLAG | partial.next().unwrap_or(0),
+        _ => flags,
+    };
+    std::iter::once(first).chain(partial).collect()
+}
+
+fn encode_child<O: AsRef<[u8]>>(child: &ChildReference<O>, out: &mut Vec<u8>) {
+    match child {
+        ChildReference::Hash(hash) => hash.as_ref().encode(out),
+        ChildReference::Inline(node, len) => out.extend_from_slice(&node.as_ref()[..*len]),
+    }
+}
+
+fn encode_list(payload: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    Header { list: true, payload_length: payload.len() }.encode(&mut out);
+    out.extend(payload);
+    out
+}
+
+fn inline_value(value: Value<'_>) -> &[u8] {
+    match value {
+        Value::Inline(value) => value,
+        Value::Node(_) => unreachable!("EthereumLayout never stores values hashed"),
+    }
+}
+
+impl<H: Hasher> NodeCodec for RlpNodeCodec<H> {
+    type Error = Error;
+    type HashOut = H::Out;
+
+    fn hashed_null_node() -> H::Out {
+        H::hash(&[EMPTY_STRING])
+    }
+
+    fn decode_plan(data: &[u8]) -> Result<NodePlan, Error> {
+        if Self::is_empty_node(data) {
+            return Ok(NodePlan::Empty)
+        }
+
+        let items = decode_list(data)?;
+        match &items[..] {
+            [partial, next] => {
+                if partial.list || partial.payload.is_empty() {
+                    return Err(Error::Custom("partial key is not hex prefix encoded"))
+                }
+                let first = data[partial.payload.start];
+                let odd = first & ODD_FLAG != 0;
+                if first >> 4 > 3 || (!odd && first & 0x0f != 0) {
+                    return Err(Error::Custom("partial key is not hex prefix encoded"))
+                }
+                // skip the flags, and for an even number of nibbles the padding nibble after them
+                let partial =
+                    NibbleSlicePlan::new(partial.payload.clone(), if odd { 1 } else { 2 });
+
+                if first & LEAF_FLAG != 0 {
+                    if next.list {
+                        return Err(Error::UnexpectedList)
+                    }
+                    Ok(NodePlan::Leaf { partial, value: ValuePlan::Inline(next.payload.clone()) })
+                } else {
+                    let child = decode_child::<H>(next)?
+                        .ok_or(Error::Custom("extension node without a child"))?;
+                    Ok(NodePlan::Extension { partial, child })
+                }
+            },
+            [slots @ .., value] if slots.len() == nibble_ops::NIBBLE_LENGTH => {
+                let mut children: [Option<NodeHandlePlan>; nibble_ops::NIBBLE_LENGTH] =
+                    Default::default();
+                for (child, slot) in children.iter_mut().zip(slots) {
+                    *child = decode_child::<H>(slot)?;
+                }
+                if value.list {
+                    return Err(Error::UnexpectedList)
+                }
+                let value =
+                    (!value.payload.is_empty()).then(|| ValuePlan::Inline(value.payload.clone()));
+                Ok(NodePlan::Branch { value, children })
+            },
+            _ => Err(Error::Custom("node is neither a leaf, an extension nor a branch")),
+        }
+    }
+
+    fn is_empty_node(data: &[u8]) -> bool {
+        data == [EMPTY_STRING]
+    }
+
+    fn empty_node() -> &'static [u8] {
+        &[EMPTY_STRING]
+    }
+
+    fn leaf_node(partial: impl Iterator<Item = u8>, number_nibble: usize, value: Value) -> Vec<u8> {
+        let mut payload = Vec::new();
+        encode_partial(partial, number_nibble, true).as_slice().encode(&mut payload);
+        inline_value(value).encode(&mut payload);
+        encode_list(payload)
+    }
+
+    fn extension_node(
+        partial: impl Iterator<Item = u8>,
+        number_nibble: usize,
+        child: ChildReference<H::Out>,
+    ) -> Vec<u8> {
+        let mut payload = Vec::new();
+        encode_partial(partial, number_nibble, false).as_slice().encode(&mut payload);
+        encode_child(&child, &mut payload);
+        encode_list(payload)
+    }
+
+    fn branch_node(
+        children: impl Iterator<Item = impl Borrow<Option<ChildReference<H::Out>>>>,
+        value: Option<Value>,
+    ) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for child in children {
+            match child.borrow() {
+                Some(child) => encode_child(child, &mut payload),
+                None => payload.push(EMPTY_STRING),
+            }
+        }
+        match value {
+            Some(value) => inline_value(value).encode(&mut payload),
+            None => payload.push(EMPTY_STRING),
+        }
+        encode_list(payload)
+    }
+
+    fn branch_node_nibbled(
+        _partial: impl Iterator<Item = u8>,
+        _number_nibble: usize,
+        _children: impl Iterator<Item = impl Borrow<Option<ChildReference<H::Out>>>>,
+        _value: Option<Value>,
+    ) -> Vec<u8> {
+        unreachable!("EthereumLayout uses extension nodes rather than nibbled branches")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkle_patricia::{ethereum_state_proof_data, ethereum_transaction_proof_data},
+        trie_utils::generate_trie_proof,
+    };
+    use hex_literal::hex;
+    use primitive_types::H256;
+    use sp_trie::StorageProof;
+    use trie_db::{Trie, TrieDBBuilder};
+
+    /// Tries built with `EthereumLayout` have the roots Ethereum clients compute, taken from the
+    /// `trieanyorder` vectors of the Ethereum tests.
+    #[test]
+    fn test_ethereum_layout_roots() {
+        let cases: [(&[(&str, &str)], [u8; 32]); 4] = [
+            (&[], hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")),
+            (
+                &[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")],
+                hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"),
+            ),
+            (
+                &[("do", "verb"), ("horse", "stallion"), ("doge", "coin"), ("dog", "puppy")],
+                hex!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"),
+            ),
+            (
+                &[("foo", "bar"), ("food", "bass")],
+                hex!("17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3"),
+            ),
+        ];
+
+        for (entries, root) in cases {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+                .collect::<Vec<_>>();
+            let proof = generate_trie_proof::<EthereumLayout>(&entries, &[]);
+            assert_eq!(proof.root, H256(root), "entries: {entries:?}");
+        }
+    }
+
+    /// `EthereumLayout` decodes mainnet proofs, finding the value of each proven key.
+    #[test]
+    fn test_ethereum_layout_reads_mainnet_proofs() {
+        for (root, proof, key, value) in
+            [ethereum_transaction_proof_data(), ethereum_state_proof_data()]
+        {
+            let db = StorageProof::new(proof).into_memory_db::<KeccakHasher>();
+            let root = H256(root);
+            let trie = TrieDBBuilder::<EthereumLayout>::new(&db, &root).build();
+            assert_eq!(trie.get(&key).unwrap(), Some(value));
+        }
+    }
+}
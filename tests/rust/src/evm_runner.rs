@@ -1,4 +1,5 @@
-use alloy_primitives::{Address, Bytes, U256};
+use alloy_primitives::{Address, Bytes, Log, U256};
+use alloy_sol_types::SolCall;
 use revm::{
     db::{CacheDB, EmptyDB},
     primitives::{AccountInfo, ExecutionResult, Output, TransactTo},
@@ -7,7 +8,7 @@ use revm::{
 
 /// Disable the contract size limit for deployments (test contracts can be large)
 const DISABLE_CONTRACT_SIZE_LIMIT: bool = true;
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 pub struct EvmRunner {
     db: CacheDB<EmptyDB>,
@@ -117,6 +118,29 @@ impl EvmRunner {
         }
     }
 
+    /// Call `to`, returning its output, the gas used and the logs it emitted.
+    pub fn call_with_logs(&mut self, to: Address, calldata: Vec<u8>) -> (Vec<u8>, u64, Vec<Log>) {
+        let result = {
+            let mut evm = Evm::builder()
+                .with_ref_db(&mut self.db)
+                .modify_tx_env(|tx| {
+                    tx.caller = self.caller;
+                    tx.transact_to = TransactTo::Call(to);
+                    tx.data = Bytes::from(calldata);
+                    tx.value = U256::ZERO;
+                    tx.gas_limit = 30_000_000;
+                })
+                .build();
+            evm.transact_commit().unwrap()
+        };
+
+        match result {
+            ExecutionResult::Success { output: Output::Call(data), gas_used, logs, .. } =>
+                (data.to_vec(), gas_used, logs),
+            other => panic!("call failed: {other:?}"),
+        }
+    }
+
     pub fn call_may_revert(&mut self, to: Address, calldata: Vec<u8>) -> Result<Vec<u8>, String> {
         let result = {
             let mut evm = Evm::builder()
@@ -138,6 +162,28 @@ impl EvmRunner {
             other => Err(format!("failed: {other:?}")),
         }
     }
+
+    /// Call `to` with `call`, decoding its return data.
+    pub fn call_sol<C: SolCall>(&mut self, to: Address, call: C) -> Result<C::Return, String> {
+        let result = self.call_may_revert(to, call.abi_encode())?;
+        C::abi_decode_returns(&result, true).map_err(|e| format!("decode error: {e}"))
+    }
+
+    /// The non-zero storage slots of the account at `address`.
+    pub fn storage(&self, address: Address) -> BTreeMap<U256, U256> {
+        self.db
+            .accounts
+            .get(&address)
+            .map(|account| {
+                account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(slot, value)| (*slot, *value))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 /// Load bytecode from a foundry artifact, deploying and linking any libraries.
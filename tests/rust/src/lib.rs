@@ -1,13 +1,31 @@
 #![allow(unused_parens, dead_code)]
 
+pub mod blake2b;
+pub mod block_header;
+pub mod block_tries;
+pub mod eip1186;
+pub mod eth_rpc;
+pub mod ethereum_storage;
+pub mod ethereum_trie;
 pub mod evm_runner;
 pub mod merkle_mountain_range;
 pub mod merkle_multi_proof;
 pub mod merkle_patricia;
+pub mod mmr_utils;
 pub mod multi_proof_utils;
+pub mod mutations;
+pub mod rlp_reader;
+pub mod scale_codec;
+pub mod state_proof;
+pub mod storage_layout;
+#[cfg(feature = "strategies")]
+pub mod strategies;
+pub mod substrate_storage;
+pub mod trie_utils;
 
 use alloy_primitives::keccak256;
 use ckb_merkle_mountain_range::{Error, Merge};
+use rand::{rngs::StdRng, SeedableRng};
 use rs_merkle::Hasher;
 
 #[derive(Clone)]
@@ -43,3 +61,15 @@ impl From<u32> for NumberHash {
         NumberHash(hash.0.to_vec())
     }
 }
+
+/// Returns a seeded rng for tests that need random data outside of a proptest strategy. The seed
+/// is read from `TEST_SEED` when set, otherwise chosen at random, and is always printed so a
+/// failing run can be replayed.
+pub fn seeded_rng() -> StdRng {
+    let seed = std::env::var("TEST_SEED")
+        .ok()
+        .map(|seed| seed.parse().expect("TEST_SEED must be a u64"))
+        .unwrap_or_else(rand::random);
+    println!("rng seed: {seed} (rerun with TEST_SEED={seed} to reproduce)");
+    StdRng::seed_from_u64(seed)
+}
@@ -2,12 +2,18 @@
 
 use crate::{
     evm_runner::{project_root, EvmRunner},
-    MergeKeccak, NumberHash,
+    mmr_utils::{generate_mmr_proof, MmrProof},
+    multi_proof_utils::Leaf,
+    mutations::{assert_small_proofs_reject, check_mutations, Mutation, MMR_WEAKNESSES},
+    seeded_rng, MergeKeccak, NumberHash,
 };
 use alloy_primitives::{FixedBytes, U256};
 use alloy_sol_types::{sol, SolCall};
 use ckb_merkle_mountain_range::{util::MemStore, MMR};
-use proptest::{prop_assert, proptest};
+use primitive_types::H256;
+use proptest::{prelude::*, sample::subsequence};
+use rand::Rng;
+use std::collections::HashSet;
 
 sol! {
     struct MmrLeaf {
@@ -204,14 +210,7 @@ fn solidity_verify_proof(
         leaves,
         leafCount: U256::from(leaf_count),
     };
-    match runner.call_may_revert(contract, call.abi_encode()) {
-        Ok(result) => {
-            let decoded = VerifyProofCall::abi_decode_returns(&result, true)
-                .map_err(|e| format!("decode error: {e}"))?;
-            Ok(decoded._0)
-        },
-        Err(e) => Err(e),
-    }
+    Ok(runner.call_sol(contract, call)?._0)
 }
 
 /// Build a valid MMR proof and return all the pieces needed for Solidity verification.
@@ -224,46 +223,33 @@ fn build_mmr_proof(
     Vec<MmrLeaf>,        // leaves
     [u8; 32],            // leaf hash
 ) {
-    let store = MemStore::default();
-    let mut mmr = MMR::<_, MergeKeccak, _>::new(0, &store);
-    let positions: Vec<u64> = (0..count).map(|i| mmr.push(NumberHash::from(i)).unwrap()).collect();
-    let root = mmr.get_root().unwrap();
-    let proof = mmr.gen_proof(vec![positions[leaf_idx as usize]]).unwrap();
-    mmr.commit().unwrap();
-
-    let leaf = NumberHash::from(leaf_idx);
-    let mut leaf_hash = [0u8; 32];
-    leaf_hash.copy_from_slice(&leaf.0);
-    let mut root_hash = [0u8; 32];
-    root_hash.copy_from_slice(&root.0);
-
-    let sol_proof: Vec<FixedBytes<32>> = proof
-        .proof_items()
-        .iter()
-        .map(|p| {
-            let mut b = [0u8; 32];
-            b.copy_from_slice(&p.0);
-            FixedBytes(b)
-        })
-        .collect();
+    let proof = generate_mmr_proof(count, &[leaf_idx]);
+    let leaf_hash = proof.leaves[0].hash.0;
+
+    (
+        proof.root.0,
+        mmr_proof_to_abi(&proof.proof_items),
+        mmr_leaves_to_abi(&proof.leaves),
+        leaf_hash,
+    )
+}
 
-    let sol_leaves = vec![MmrLeaf { index: U256::from(leaf_idx), hash: FixedBytes(leaf_hash) }];
+fn mmr_proof_to_abi(proof_items: &[H256]) -> Vec<FixedBytes<32>> {
+    proof_items.iter().map(|h| FixedBytes(h.0)).collect()
+}
 
-    (root_hash, sol_proof, sol_leaves, leaf_hash)
+fn mmr_leaves_to_abi(leaves: &[Leaf]) -> Vec<MmrLeaf> {
+    leaves
+        .iter()
+        .map(|l| MmrLeaf { index: U256::from(l.index), hash: FixedBytes(l.hash.0) })
+        .collect()
 }
 
 proptest! {
     #[test]
-    fn test_random_mmr(count in 10u32..500u32) {
-        use rand::seq::SliceRandom;
-        use rand::Rng;
-
-        let mut leaves: Vec<u32> = (0..count).collect();
-        let mut rng = rand::thread_rng();
-        leaves.shuffle(&mut rng);
-        let leaves_count = rng.gen_range(1..count - 1);
-        leaves.truncate(leaves_count as usize);
-
+    fn test_random_mmr((count, leaves) in (10u32..500u32).prop_flat_map(|count| {
+        (Just(count), subsequence((0..count).collect::<Vec<_>>(), 1..(count as usize - 1)))
+    })) {
         let (mut runner, addr) = setup();
         test_mmr(&mut runner, addr, count, leaves);
     }
@@ -366,11 +352,94 @@ proptest! {
     }
 }
 
+/// Whether `mutated` still verifies against the root of `proof`, which it was made from.
+fn verifies_mutated(
+    runner: &mut EvmRunner,
+    addr: alloy_primitives::Address,
+    proof: &MmrProof,
+    mutated: &MmrProof,
+) -> bool {
+    solidity_verify_proof(
+        runner,
+        addr,
+        proof.root.0,
+        mmr_proof_to_abi(&mutated.proof_items),
+        mmr_leaves_to_abi(&mutated.leaves),
+        mutated.leaf_count,
+    )
+    .unwrap_or(false)
+}
+
+fn assert_small_mmr_proofs_reject(mutations: &[Mutation]) {
+    let (mut runner, addr) = setup();
+    assert_small_proofs_reject(
+        1..=32,
+        mutations,
+        |count, indices| {
+            let indices = indices.iter().map(|index| *index as u32).collect::<Vec<_>>();
+            generate_mmr_proof(count as u32, &indices)
+        },
+        |proof, mutated| verifies_mutated(&mut runner, addr, proof, mutated),
+    );
+}
+
+/// No mutation besides the known weaknesses of the verifier verifies for a proof of a small tree.
 #[test]
-fn test_mmr_gas_benchmark() {
-    use rand::Rng;
+fn test_small_mutated_mmr_proofs() {
+    assert_small_mmr_proofs_reject(&Mutation::all_except(MMR_WEAKNESSES));
+}
+
+/// No mutation at all verifies for a proof of a small tree, which fails on the known weaknesses of
+/// the verifier until they are fixed.
+#[test]
+#[ignore = "the verifier accepts the mutations in MMR_WEAKNESSES"]
+fn test_small_mmr_proofs_reject_every_mutation() {
+    assert_small_mmr_proofs_reject(&Mutation::ALL);
+}
+
+proptest! {
+    /// No mutation of a valid proof may verify against the original root, besides the known
+    /// weaknesses of the verifier.
+    #[test]
+    fn test_mutated_mmr_proof(
+        (count, indices) in (1u32..200u32).prop_flat_map(|count| {
+            (Just(count), subsequence((0..count).collect::<Vec<_>>(), 1..=count as usize))
+        }),
+        seed in any::<u64>(),
+    ) {
+        let proof = generate_mmr_proof(count, &indices);
+        let (mut runner, addr) = setup();
+        let mutations = Mutation::all_except(MMR_WEAKNESSES);
+        let report = check_mutations(&proof, &mutations, &[], seed, |mutated| {
+            verifies_mutated(&mut runner, addr, &proof, mutated)
+        });
+        prop_assert!(report.accepted.is_empty(), "mutations verified for count={count}: {:?}", report.accepted);
+    }
+}
 
+#[cfg(feature = "strategies")]
+proptest! {
+    /// Proofs for any subset of leaves must verify.
+    #[test]
+    fn test_arb_mmr_proof(proof in crate::strategies::arb_mmr_proof(200)) {
+        let (mut runner, addr) = setup();
+        let verified = solidity_verify_proof(
+            &mut runner,
+            addr,
+            proof.root.0,
+            mmr_proof_to_abi(&proof.proof_items),
+            mmr_leaves_to_abi(&proof.leaves),
+            proof.leaf_count,
+        )
+        .expect("VerifyProof should not revert for valid proof");
+        prop_assert!(verified, "valid proof rejected for leaf_count={}", proof.leaf_count);
+    }
+}
+
+#[test]
+fn test_mmr_gas_benchmark() {
     let (mut runner, contract) = setup();
+    let mut rng = seeded_rng();
 
     for count in [8u32, 32, 64, 128, 256, 512, 1024] {
         let store = MemStore::default();
@@ -380,8 +449,7 @@ fn test_mmr_gas_benchmark() {
         let root = mmr.get_root().unwrap();
 
         let threshold = std::cmp::max(1, count / 3);
-        let mut rng = rand::thread_rng();
-        let mut indices_set = std::collections::HashSet::new();
+        let mut indices_set = HashSet::new();
         while indices_set.len() < threshold as usize {
             indices_set.insert(rng.gen_range(0..count));
         }
@@ -3,13 +3,14 @@
 
 use crate::{
     evm_runner::{project_root, EvmRunner},
-    multi_proof_utils::{Leaf, RsMerkleProof, SolidityProof},
-    Keccak256,
+    multi_proof_utils::{generate_multi_proof, Leaf, MultiProof, RsMerkleProof, SolidityProof},
+    mutations::{assert_small_proofs_reject, check_mutations, Mutation, MULTI_PROOF_WEAKNESSES},
+    seeded_rng, Keccak256,
 };
 use alloy_primitives::{keccak256, FixedBytes, U256};
 use alloy_sol_types::{sol, SolCall};
 use primitive_types::H256;
-use proptest::{prop_assert, prop_assert_eq, prop_assert_ne, proptest};
+use proptest::{prelude::*, sample::subsequence};
 use rand::Rng;
 use rs_merkle::MerkleTree;
 use std::collections::HashSet;
@@ -62,25 +63,18 @@ fn solidity_calc_root_raw(
         leaves: leaves_to_abi(&proof.leaves),
         numLeaves: U256::from(num_leaves),
     };
-    match runner.call_may_revert(contract, call.abi_encode()) {
-        Ok(result) => {
-            let decoded = CalculateRootCall::abi_decode_returns(&result, true)
-                .map_err(|e| format!("decode: {e}"))?;
-            Ok(decoded._0.0)
-        },
-        Err(e) => Err(e),
-    }
+    Ok(runner.call_sol(contract, call)?._0.0)
 }
 
 #[test]
 fn test_calculate_root() {
     let num_leaves = 600;
     let threshold = ((num_leaves * 1) / 3) - 1;
-    let leaves = (0..num_leaves).map(|_| H256::random().as_bytes().to_vec()).collect::<Vec<_>>();
+    let mut rng = seeded_rng();
+    let leaves = (0..num_leaves).map(|_| rng.gen::<[u8; 32]>().to_vec()).collect::<Vec<_>>();
     let leaf_hashes = leaves.iter().map(|l| keccak256(l).0).collect::<Vec<[u8; 32]>>();
 
     let tree = MerkleTree::<Keccak256>::from_leaves(&leaf_hashes);
-    let mut rng = rand::thread_rng();
     let mut indices = HashSet::new();
     while indices.len() < threshold {
         indices.insert(rng.gen_range(0..num_leaves));
@@ -116,12 +110,12 @@ fn test_calculate_root() {
 fn test_rs_merkle_proof_conversion() {
     let num_leaves = 600;
     let threshold = ((num_leaves * 1) / 3) - 1;
-    let leaves = (0..num_leaves).map(|_| H256::random().as_bytes().to_vec()).collect::<Vec<_>>();
+    let mut rng = seeded_rng();
+    let leaves = (0..num_leaves).map(|_| rng.gen::<[u8; 32]>().to_vec()).collect::<Vec<_>>();
     let leaf_hashes = leaves.iter().map(|l| keccak256(l).0).collect::<Vec<[u8; 32]>>();
 
     let tree = MerkleTree::<Keccak256>::from_leaves(&leaf_hashes);
 
-    let mut rng = rand::thread_rng();
     let mut indices_set = HashSet::new();
     while indices_set.len() < threshold {
         indices_set.insert(rng.gen_range(0..num_leaves));
@@ -158,19 +152,10 @@ fn build_multi_proof(
     SolidityProof, // converted proof
     [u8; 32],      // leaf hash
 ) {
-    let leaf_hashes: Vec<[u8; 32]> =
-        (0..num_leaves).map(|i| keccak256(&(i as u32).to_le_bytes()).0).collect();
-    let tree = MerkleTree::<Keccak256>::from_leaves(&leaf_hashes);
-    let root = tree.root().unwrap();
-
-    let proof = tree.proof(&[leaf_idx]);
-    let sol_proof = SolidityProof::from(RsMerkleProof {
-        proof: &proof,
-        leaf_indices: &[leaf_idx],
-        leaf_hashes: &[leaf_hashes[leaf_idx]],
-    });
+    let MultiProof { root, proof, .. } = generate_multi_proof(num_leaves, &[leaf_idx]);
+    let leaf_hash = proof.leaves[0].hash.0;
 
-    (root, sol_proof, leaf_hashes[leaf_idx])
+    (root.0, proof, leaf_hash)
 }
 
 proptest! {
@@ -308,11 +293,87 @@ proptest! {
     }
 }
 
+/// Whether `mutated` still produces the root of `proof`, which it was made from.
+fn verifies_mutated(
+    runner: &mut EvmRunner,
+    contract: alloy_primitives::Address,
+    proof: &MultiProof,
+    mutated: &MultiProof,
+) -> bool {
+    solidity_calc_root_raw(runner, contract, &mutated.proof, mutated.num_leaves)
+        .is_ok_and(|calc| calc == proof.root.0)
+}
+
+fn setup() -> (EvmRunner, alloy_primitives::Address) {
+    let project = project_root();
+    let mut runner = EvmRunner::new();
+    let contract = runner.deploy(&project, "MerkleMultiProofTest");
+    (runner, contract)
+}
+
+fn assert_small_multi_proofs_reject(mutations: &[Mutation]) {
+    let (mut runner, contract) = setup();
+    assert_small_proofs_reject(2..=32, mutations, generate_multi_proof, |proof, mutated| {
+        verifies_mutated(&mut runner, contract, proof, mutated)
+    });
+}
+
+/// No mutation besides the known weaknesses of the verifier verifies for a proof of a small tree.
+#[test]
+fn test_small_mutated_multi_proofs() {
+    assert_small_multi_proofs_reject(&Mutation::all_except(MULTI_PROOF_WEAKNESSES));
+}
+
+/// No mutation at all verifies for a proof of a small tree, which fails on the known weaknesses of
+/// the verifier until they are fixed.
+#[test]
+#[ignore = "the verifier accepts the mutations in MULTI_PROOF_WEAKNESSES"]
+fn test_small_multi_proofs_reject_every_mutation() {
+    assert_small_multi_proofs_reject(&Mutation::ALL);
+}
+
+proptest! {
+    /// No mutation of a valid proof may produce the original root, besides the known weaknesses of
+    /// the verifier.
+    #[test]
+    fn test_mutated_multi_proof(
+        (num_leaves, indices) in (2usize..200).prop_flat_map(|num_leaves| {
+            (Just(num_leaves), subsequence((0..num_leaves).collect::<Vec<_>>(), 1..=num_leaves))
+        }),
+        seed in any::<u64>(),
+    ) {
+        let proof = generate_multi_proof(num_leaves, &indices);
+        let (mut runner, contract) = setup();
+        let mutations = Mutation::all_except(MULTI_PROOF_WEAKNESSES);
+        let report = check_mutations(&proof, &mutations, &[], seed, |mutated| {
+            verifies_mutated(&mut runner, contract, &proof, mutated)
+        });
+        prop_assert!(report.accepted.is_empty(), "mutations matched root for num_leaves={num_leaves}: {:?}", report.accepted);
+    }
+}
+
+#[cfg(feature = "strategies")]
+proptest! {
+    /// Multi proofs for any subset of leaves must produce matching roots.
+    #[test]
+    fn test_arb_multi_proof(proof in crate::strategies::arb_multi_proof(200)) {
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let contract = runner.deploy(&project, "MerkleMultiProofTest");
+
+        let calculated =
+            solidity_calc_root_raw(&mut runner, contract, &proof.proof, proof.num_leaves)
+                .expect("CalculateRoot should not revert for valid proof");
+        prop_assert_eq!(calculated, proof.root.0);
+    }
+}
+
 #[test]
 fn test_gas_benchmark() {
     let project = project_root();
     let mut runner = EvmRunner::new();
     let contract = runner.deploy(&project, "MerkleMultiProofTest");
+    let mut rng = seeded_rng();
 
     for num_leaves in [8, 32, 64, 128, 256, 512, 1024] {
         let leaf_hashes: Vec<[u8; 32]> =
@@ -321,7 +382,6 @@ fn test_gas_benchmark() {
 
         // Prove ~1/3 of leaves
         let threshold = std::cmp::max(1, num_leaves / 3);
-        let mut rng = rand::thread_rng();
         let mut indices_set = HashSet::new();
         while indices_set.len() < threshold {
             indices_set.insert(rng.gen_range(0..num_leaves));
@@ -1,18 +1,25 @@
 #![cfg(test)]
 #![allow(dead_code, unused_imports)]
 
-use crate::evm_runner::{project_root, EvmRunner};
+use crate::{
+    ethereum_trie::EthereumLayout,
+    evm_runner::{project_root, EvmRunner},
+    mutations::{check_mutations, Mutation, MutationReport, TRIE_KNOWN_ACCEPTED},
+    seeded_rng,
+    trie_utils::{generate_trie_proof, TrieProof},
+};
 use alloy_primitives::{FixedBytes, U256};
 use alloy_sol_types::{sol, SolCall, SolValue};
 use codec::Decode;
 use hex_literal::hex;
 use primitive_types::H256;
+use proptest::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use sp_core::KeccakHasher;
-use sp_trie::{LayoutV0, MemoryDB, NodeCodec, StorageProof};
-use std::collections::HashSet;
+use sp_trie::{LayoutV0, LayoutV1, NodeCodec, StorageProof};
+use std::collections::BTreeMap;
 use trie_db::{
-    DBValue, Hasher, NodeCodec as NodeCodecT, Recorder, Trie, TrieDBBuilder, TrieDBMutBuilder,
-    TrieLayout, TrieMut,
+    DBValue, Hasher, NibbleSlice, NodeCodec as NodeCodecT, Trie, TrieDBBuilder, TrieLayout,
 };
 
 sol! {
@@ -57,6 +64,10 @@ sol! {
     }
 
     function VerifyKeys(bytes32 root, bytes[] proof, bytes[] keys) external pure returns (StorageValue[]);
+    function VerifyKeysBlake2(bytes32 root, bytes[] proof, bytes[] keys) external view returns (StorageValue[]);
+    function ReadChild(bytes32 root, bytes[] proof, bytes[] keys, bytes childInfo) external pure returns (StorageValue[]);
+    function ReadChildBlake2(bytes32 root, bytes[] proof, bytes[] keys, bytes childInfo) external view returns (StorageValue[]);
+    function blake2b256(bytes data) external view returns (bytes32);
     function VerifyEthereum(bytes32 root, bytes[] proof, bytes[] keys) external pure returns (StorageValue[]);
     function decodeNodeKind(bytes node) external pure returns (SolNodeKind);
     function decodeNibbledBranch(bytes node) external;
@@ -68,9 +79,10 @@ sol! {
     function commonPrefix(SolNibbleSlice self_, SolNibbleSlice other) external pure returns (uint256);
     function startsWith(SolNibbleSlice self_, SolNibbleSlice other) external pure returns (bool);
     function eq(SolNibbleSlice self_, SolNibbleSlice other) external pure returns (bool);
+    function bytesSlice(bytes data, uint256 start) external pure returns (bytes);
 }
 
-fn proof_data() -> ([u8; 32], Vec<Vec<u8>>, Vec<u8>) {
+pub(crate) fn proof_data() -> ([u8; 32], Vec<Vec<u8>>, Vec<u8>) {
     let key = hex!("f0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb").to_vec();
     let proof = vec![
         hex!("802e98809b03c6ae83e3b70aa89acfe0947b3a18b5d35569662335df7127ab8fcb88c88780e5d1b21c5ecc2891e3467f6273f27ce2e73a292d6b8306197edfa97b3d965bd080c51e5f53a03d92ea8b2792218f152da738b9340c6eeb08581145825348bbdba480ad103a9320581c7747895a01d79d2fa5f103c4b83c5af10b0a13bc1749749523806eea23c0854ced8445a3338833e2401753fdcfadb3b56277f8f1af4004f73719806d990657a5b5c3c97b8a917d9f153cafc463acd90592f881bc071d6ba64e90b380346031472f91f7c44631224cb5e61fb29d530a9fafd5253551cbf43b7e97e79a").to_vec(),
@@ -82,13 +94,21 @@ fn proof_data() -> ([u8; 32], Vec<Vec<u8>>, Vec<u8>) {
     (root, proof, key)
 }
 
-fn setup() -> (EvmRunner, alloy_primitives::Address) {
+pub(crate) fn setup() -> (EvmRunner, alloy_primitives::Address) {
     let root = project_root();
     let mut runner = EvmRunner::new();
     let addr = runner.deploy(&root, "MerklePatriciaTest");
     (runner, addr)
 }
 
+pub(crate) fn assert_values(values: &[StorageValue], items: &[(Vec<u8>, Option<Vec<u8>>)]) {
+    assert_eq!(values.len(), items.len());
+    for (value, (key, expected)) in values.iter().zip(items) {
+        assert_eq!(&value.key.to_vec(), key);
+        assert_eq!(value.value.to_vec(), expected.clone().unwrap_or_default());
+    }
+}
+
 fn make_nibble(data: &[u8], offset: u64) -> SolNibbleSlice {
     SolNibbleSlice { data: data.to_vec().into(), offset: U256::from(offset) }
 }
@@ -267,6 +287,141 @@ fn test_nibble_slice_ops_shared() {
     assert_eq!(commonPrefixCall::abi_decode_returns(&result, true).unwrap()._0, U256::from(0));
 }
 
+/// A byte string and a nibble offset into it, covering odd offsets and empty slices.
+fn arb_nibble_slice() -> impl Strategy<Value = (Vec<u8>, usize)> {
+    prop::collection::vec(any::<u8>(), 0..12).prop_flat_map(|data| {
+        let nibbles = data.len() * 2;
+        (Just(data), 0..=nibbles)
+    })
+}
+
+/// Pack `nibbles` into bytes behind `pad` filler bytes and, for an odd count, a filler nibble.
+fn pack_nibbles(nibbles: &[u8], pad: usize, filler: u8) -> (Vec<u8>, usize) {
+    let offset = pad * 2 + nibbles.len() % 2;
+    let padded = std::iter::repeat(filler).take(offset).chain(nibbles.iter().copied());
+    let data = padded
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect();
+    (data, offset)
+}
+
+fn nibbles_of(slice: &NibbleSlice) -> Vec<u8> {
+    (0..slice.len()).map(|i| slice.at(i)).collect()
+}
+
+/// Two slices, the second either unrelated to the first or sharing a prefix with it at any
+/// alignment.
+fn arb_nibble_slice_pair() -> impl Strategy<Value = ((Vec<u8>, usize), (Vec<u8>, usize))> {
+    let related = (
+        arb_nibble_slice(),
+        any::<prop::sample::Index>(),
+        prop::collection::vec(0u8..16, 0..8),
+        0usize..2,
+        0u8..16,
+    )
+        .prop_map(|((data, offset), shared, tail, pad, filler)| {
+            let nibbles = nibbles_of(&NibbleSlice::new_offset(&data, offset));
+            let shared = shared.index(nibbles.len() + 1);
+            let other = [&nibbles[..shared], &tail[..]].concat();
+            ((data, offset), pack_nibbles(&other, pad, filler))
+        });
+    prop_oneof![related, (arb_nibble_slice(), arb_nibble_slice())]
+}
+
+// NibbleSliceOps must agree with trie-db's NibbleSlice on every slice it can describe.
+proptest! {
+    #[test]
+    fn test_nibble_slice_len_and_at((data, offset) in arb_nibble_slice()) {
+        let (mut runner, addr) = setup();
+        let slice = NibbleSlice::new_offset(&data, offset);
+        let nibble = make_nibble(&data, offset as u64);
+
+        let call = nibbleLenCall { nibble: nibble.clone() };
+        let len = nibbleLenCall::abi_decode_returns(&runner.call_raw(addr, call.abi_encode()), true)
+            .unwrap()
+            ._0;
+        prop_assert_eq!(len, U256::from(slice.len()));
+
+        let call = isNibbleEmptyCall { self_: nibble.clone() };
+        let result = runner.call_raw(addr, call.abi_encode());
+        let empty = isNibbleEmptyCall::abi_decode_returns(&result, true).unwrap()._0;
+        prop_assert_eq!(empty, slice.is_empty());
+
+        for i in 0..slice.len() {
+            let call = nibbleAtCall { self_: nibble.clone(), i: U256::from(i) };
+            let result = runner.call_raw(addr, call.abi_encode());
+            let at = nibbleAtCall::abi_decode_returns(&result, true).unwrap()._0;
+            prop_assert_eq!(at, U256::from(slice.at(i)));
+        }
+    }
+
+    #[test]
+    fn test_nibble_slice_mid(
+        (data, offset) in arb_nibble_slice(),
+        i in any::<prop::sample::Index>(),
+    ) {
+        let (mut runner, addr) = setup();
+        let slice = NibbleSlice::new_offset(&data, offset);
+        let i = i.index(slice.len() + 1);
+
+        let call = midCall { self_: make_nibble(&data, offset as u64), i: U256::from(i) };
+        let mid = midCall::abi_decode_returns(&runner.call_raw(addr, call.abi_encode()), true)
+            .unwrap()
+            ._0;
+        let mid = NibbleSlice::new_offset(&mid.data, mid.offset.to());
+        prop_assert_eq!(nibbles_of(&mid), nibbles_of(&slice.mid(i)));
+    }
+
+    #[test]
+    fn test_nibble_slice_comparisons(((a, a_offset), (b, b_offset)) in arb_nibble_slice_pair()) {
+        let (mut runner, addr) = setup();
+        let (a_slice, b_slice) =
+            (NibbleSlice::new_offset(&a, a_offset), NibbleSlice::new_offset(&b, b_offset));
+        let (a, b) = (make_nibble(&a, a_offset as u64), make_nibble(&b, b_offset as u64));
+
+        // both orders, to cover a shorter slice on either side
+        let pairs = [((&a, &a_slice), (&b, &b_slice)), ((&b, &b_slice), (&a, &a_slice))];
+        for ((x, x_slice), (y, y_slice)) in pairs {
+            let call = commonPrefixCall { self_: x.clone(), other: y.clone() };
+            let result = runner.call_raw(addr, call.abi_encode());
+            let common = commonPrefixCall::abi_decode_returns(&result, true).unwrap()._0;
+            prop_assert_eq!(common, U256::from(x_slice.common_prefix(y_slice)));
+
+            let call = startsWithCall { self_: x.clone(), other: y.clone() };
+            let result = runner.call_raw(addr, call.abi_encode());
+            let starts_with = startsWithCall::abi_decode_returns(&result, true).unwrap()._0;
+            prop_assert_eq!(starts_with, x_slice.starts_with(y_slice));
+
+            let call = eqCall { self_: x.clone(), other: y.clone() };
+            let eq = eqCall::abi_decode_returns(&runner.call_raw(addr, call.abi_encode()), true)
+                .unwrap()
+                ._0;
+            prop_assert_eq!(eq, x_slice == y_slice);
+        }
+    }
+
+    #[test]
+    fn test_nibble_slice_bytes_slice(
+        data in prop::collection::vec(any::<u8>(), 0..80),
+        start in any::<prop::sample::Index>(),
+    ) {
+        let (mut runner, addr) = setup();
+        // starts up to one past the end, which must revert
+        let start = start.index(data.len() + 2);
+
+        let call = bytesSliceCall { data: data.clone().into(), start: U256::from(start) };
+        match runner.call_may_revert(addr, call.abi_encode()) {
+            Ok(result) => {
+                let slice = bytesSliceCall::abi_decode_returns(&result, true).unwrap()._0;
+                prop_assert_eq!(slice.to_vec(), data[start..].to_vec());
+            },
+            Err(_) => prop_assert!(start > data.len()),
+        }
+    }
+}
+
 #[test]
 fn test_merkle_patricia_trie() {
     let (root, proof, key) = proof_data();
@@ -285,64 +440,54 @@ fn test_merkle_patricia_trie() {
     assert_eq!(timestamp, 1_677_168_798_005);
 }
 
-fn generate_proof<L: TrieLayout>(
+pub(crate) fn generate_proof<L: TrieLayout>(
+    rng: &mut impl Rng,
 ) -> (<L::Hash as Hasher>::Out, Vec<Vec<u8>>, Vec<(Vec<u8>, Option<DBValue>)>) {
-    let keys = (0..10).map(|_| H256::random().as_bytes().to_vec()).collect::<Vec<_>>();
-    let values = (0..10).map(|_| H256::random().as_bytes().to_vec()).collect::<Vec<_>>();
-
-    let entries = keys.clone().into_iter().zip(values.clone().into_iter()).collect::<Vec<_>>();
-    let (db, root) = {
-        let mut db = <MemoryDB<L::Hash>>::default();
-        let mut root = Default::default();
-        {
-            let mut trie = TrieDBMutBuilder::<L>::new(&mut db, &mut root).build();
-            for (key, value) in &entries {
-                trie.insert(key, value).unwrap();
-            }
-        }
-        (db, root)
-    };
-
-    let proof = {
-        let mut recorder = Recorder::<L>::new();
-        let trie_db = TrieDBBuilder::<L>::new(&db, &root).with_recorder(&mut recorder).build();
-
-        for (key, expected) in &entries {
-            let value = trie_db.get(key).unwrap().unwrap();
-            assert_eq!(&value, expected);
-        }
+    // values either side of the 32 byte threshold above which `LayoutV1` stores them hashed
+    let entries = (0..10)
+        .map(|_| {
+            let value = (0..rng.gen_range(1..=64)).map(|_| rng.gen()).collect();
+            (rng.gen::<[u8; 32]>().to_vec(), value)
+        })
+        .collect::<Vec<_>>();
+    let keys = entries.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
 
-        let proof = recorder.drain().into_iter().map(|f| f.data).collect::<HashSet<_>>();
-        {
-            let mdb = StorageProof::new(proof.clone()).into_memory_db::<L::Hash>();
-            let trie_db = TrieDBBuilder::<L>::new(&mdb, &root).build();
-            for (key, expected) in &entries {
-                let value = trie_db.get(key).unwrap().unwrap();
-                assert_eq!(&value, expected);
-            }
-        }
+    let TrieProof { root, proof, items } = generate_trie_proof::<L>(&entries, &keys);
 
-        proof.into_iter().collect::<Vec<_>>()
-    };
+    (root, proof, items)
+}
 
-    let trie = TrieDBBuilder::<L>::new(&db, &root).build();
-    let items = keys
-        .into_iter()
-        .map(|key| {
-            let value = trie.get(&key).unwrap();
-            (key, value)
+/// The number of trie nodes in `proof` that refer to their value by hash, as decoded by the
+/// Solidity decoder. Value nodes, which are not trie nodes, are skipped.
+fn hashed_value_nodes(
+    runner: &mut EvmRunner,
+    addr: alloy_primitives::Address,
+    proof: &[Vec<u8>],
+    items: &[(Vec<u8>, Option<DBValue>)],
+) -> usize {
+    proof
+        .iter()
+        .filter(|node| !items.iter().any(|(_, value)| value.as_ref() == Some(*node)))
+        .filter(|node| {
+            let call = decodeNodeKindCall { node: node.to_vec().into() };
+            let result = runner.call_raw(addr, call.abi_encode());
+            let kind = decodeNodeKindCall::abi_decode_returns(&result, true).unwrap()._0;
+            kind.isHashedLeaf || kind.isNibbledHashedValueBranch
         })
-        .collect();
-
-    (root, proof, items)
+        .count()
 }
 
-#[test]
-fn test_merkle_patricia_trie_layout_v0() {
-    let (root, proof, entries) = generate_proof::<LayoutV0<KeccakHasher>>();
+/// Verify every key of a generated proof, and a key absent from the trie. Returns the number of
+/// nodes that hold a hashed value and the number of values over 32 bytes.
+fn verify_generated_proof<L: TrieLayout>() -> (usize, usize)
+where
+    <L::Hash as Hasher>::Out: Into<[u8; 32]>,
+{
+    let mut rng = seeded_rng();
+    let (root, proof, entries) = generate_proof::<L>(&mut rng);
     let (mut runner, addr) = setup();
 
-    for (key, value) in entries {
+    for (key, value) in entries.clone() {
         let call = VerifyKeysCall {
             root: FixedBytes(root.into()),
             proof: proof.clone().into_iter().map(Into::into).collect(),
@@ -356,12 +501,205 @@ fn test_merkle_patricia_trie_layout_v0() {
     // non-membership proof
     let call = VerifyKeysCall {
         root: FixedBytes(root.into()),
-        proof: proof.into_iter().map(Into::into).collect(),
-        keys: vec![H256::random().as_bytes().to_vec().into()],
+        proof: proof.clone().into_iter().map(Into::into).collect(),
+        keys: vec![rng.gen::<[u8; 32]>().to_vec().into()],
     };
     let result = runner.call_raw(addr, call.abi_encode());
     let decoded = VerifyKeysCall::abi_decode_returns(&result, true).unwrap();
     assert_eq!(decoded._0[0].value.len(), 0);
+
+    let long_values = entries.iter().filter(|(_, value)| value.as_ref().unwrap().len() > 32);
+    (hashed_value_nodes(&mut runner, addr, &proof, &entries), long_values.count())
+}
+
+#[test]
+fn test_merkle_patricia_trie_layout_v0() {
+    let (hashed, _) = verify_generated_proof::<LayoutV0<KeccakHasher>>();
+    assert_eq!(hashed, 0);
+}
+
+#[test]
+fn test_merkle_patricia_trie_layout_v1() {
+    let (hashed, long_values) = verify_generated_proof::<LayoutV1<KeccakHasher>>();
+    assert_eq!(hashed, long_values);
+}
+
+/// Each value over 32 bytes is stored hashed under `LayoutV1`, in a hashed leaf or in a branch
+/// with a hashed value.
+#[test]
+fn test_merkle_patricia_trie_layout_v1_hashed_values() {
+    let entries = (0u8..16)
+        .map(|i| (vec![i << 4, i], vec![i; if i % 2 == 0 { 33 } else { 32 }]))
+        // a branch holding a hashed value, with its children below it
+        .chain([(vec![0x10], vec![0xff; 64]), (vec![0x20], vec![0xff; 16])])
+        .collect::<Vec<_>>();
+    let keys = entries.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+    let TrieProof { root, proof, items } =
+        generate_trie_proof::<LayoutV1<KeccakHasher>>(&entries, &keys);
+    let (mut runner, addr) = setup();
+
+    let call = VerifyKeysCall {
+        root: FixedBytes(root.into()),
+        proof: proof.iter().cloned().map(Into::into).collect(),
+        keys: keys.iter().cloned().map(Into::into).collect(),
+    };
+    let result = runner.call_raw(addr, call.abi_encode());
+    let decoded = VerifyKeysCall::abi_decode_returns(&result, true).unwrap();
+    for (value, (_, expected)) in decoded._0.iter().zip(&items) {
+        assert_eq!(Some(value.value.to_vec()), *expected);
+    }
+
+    let hashed = entries.iter().filter(|(_, value)| value.len() > 32).count();
+    assert_eq!(hashed_value_nodes(&mut runner, addr, &proof, &items), hashed);
+}
+
+/// Mutate a generated proof every way we know of, reporting the mutations that still verified the
+/// original values.
+fn mutated_substrate_trie_proof<L: TrieLayout>(seed: u64) -> MutationReport
+where
+    <L::Hash as Hasher>::Out: Into<[u8; 32]>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (root, proof, items) = generate_proof::<L>(&mut rng);
+    let proof = TrieProof { root, proof, items };
+
+    let (mut runner, addr) = setup();
+    check_mutations(&proof, &Mutation::ALL, TRIE_KNOWN_ACCEPTED, seed, |mutated| {
+        let call = VerifyKeysCall {
+            root: FixedBytes(root.into()),
+            proof: mutated.proof.iter().cloned().map(Into::into).collect(),
+            keys: mutated.items.iter().map(|(key, _)| key.clone().into()).collect(),
+        };
+        let Ok(result) = runner.call_may_revert(addr, call.abi_encode()) else { return false };
+        let decoded = VerifyKeysCall::abi_decode_returns(&result, true).unwrap();
+        decoded._0.iter().zip(&mutated.items).all(|(value, (_, expected))| {
+            value.value.to_vec() == expected.clone().unwrap_or_default()
+        })
+    })
+}
+
+/// The trie verifier accepts the mutations it is known to whenever they apply, as they leave the
+/// set of nodes, and so the proven values, unchanged.
+#[test]
+fn test_substrate_trie_known_accepted_mutations() {
+    for seed in 0..8 {
+        for report in [
+            mutated_substrate_trie_proof::<LayoutV0<KeccakHasher>>(seed),
+            mutated_substrate_trie_proof::<LayoutV1<KeccakHasher>>(seed),
+        ] {
+            let applied = report.applied.iter().filter(|m| TRIE_KNOWN_ACCEPTED.contains(m));
+            assert_eq!(report.known_accepted, applied.copied().collect::<Vec<_>>());
+            assert!(report.accepted.is_empty(), "mutations verified: {:?}", report.accepted);
+        }
+    }
+}
+
+proptest! {
+    /// No mutation of a valid proof may verify the original values, besides those the verifier is
+    /// known to accept.
+    #[test]
+    fn test_mutated_substrate_trie_proof(seed in any::<u64>()) {
+        let report = mutated_substrate_trie_proof::<LayoutV0<KeccakHasher>>(seed);
+        prop_assert!(report.accepted.is_empty(), "mutations verified: {:?}", report.accepted);
+    }
+
+    /// No mutation of a valid `LayoutV1` proof, including of its value nodes, may verify the
+    /// original values, besides those the verifier is known to accept.
+    #[test]
+    fn test_mutated_substrate_trie_proof_v1(seed in any::<u64>()) {
+        let report = mutated_substrate_trie_proof::<LayoutV1<KeccakHasher>>(seed);
+        prop_assert!(report.accepted.is_empty(), "mutations verified: {:?}", report.accepted);
+    }
+}
+
+/// Mutate a generated Ethereum proof every way we know of, reporting the mutations that still
+/// verified the original values. Keys of a byte or two, along with short values, leave some nodes
+/// embedded in their parent, while keys of 32 bytes keep the others hashed.
+fn mutated_ethereum_trie_proof(seed: u64) -> MutationReport {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let entries = (0..rng.gen_range(1..32))
+        .map(|_| {
+            let key_len = if rng.gen() { 32 } else { rng.gen_range(1..=2) };
+            let key = (0..key_len).map(|_| rng.gen()).collect();
+            let value = (0..rng.gen_range(1..=64)).map(|_| rng.gen()).collect();
+            (key, value)
+        })
+        .collect::<BTreeMap<Vec<u8>, Vec<u8>>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let keys = entries.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+    let proof = generate_trie_proof::<EthereumLayout>(&entries, &keys);
+
+    let (mut runner, addr) = setup();
+    check_mutations(&proof, &Mutation::ALL, TRIE_KNOWN_ACCEPTED, seed, |mutated| {
+        let call = VerifyEthereumCall {
+            root: FixedBytes(proof.root.into()),
+            proof: mutated.proof.iter().cloned().map(Into::into).collect(),
+            keys: mutated.items.iter().map(|(key, _)| key.clone().into()).collect(),
+        };
+        let Ok(decoded) = runner.call_sol(addr, call) else { return false };
+        decoded._0.iter().zip(&mutated.items).all(|(value, (_, expected))| {
+            value.value.to_vec() == expected.clone().unwrap_or_default()
+        })
+    })
+}
+
+/// The Ethereum verifier accepts the mutations the trie verifiers are known to whenever they
+/// apply, and no other.
+#[test]
+fn test_ethereum_trie_known_accepted_mutations() {
+    for seed in 0..16 {
+        let report = mutated_ethereum_trie_proof(seed);
+        let applied = report.applied.iter().filter(|m| TRIE_KNOWN_ACCEPTED.contains(m));
+        assert_eq!(report.known_accepted, applied.copied().collect::<Vec<_>>());
+        assert!(report.accepted.is_empty(), "mutations verified: {:?}", report.accepted);
+    }
+}
+
+proptest! {
+    /// No mutation of a valid Ethereum proof may verify the original values, besides those the
+    /// trie verifiers are known to accept.
+    #[test]
+    fn test_mutated_ethereum_trie_proof(seed in any::<u64>()) {
+        let report = mutated_ethereum_trie_proof(seed);
+        prop_assert!(report.accepted.is_empty(), "mutations verified: {:?}", report.accepted);
+    }
+}
+
+#[cfg(feature = "strategies")]
+fn verify_arb_substrate_trie_proof(proof: TrieProof<H256>) -> Result<(), TestCaseError> {
+    let (mut runner, addr) = setup();
+    let call = VerifyKeysCall {
+        root: FixedBytes(proof.root.into()),
+        proof: proof.proof.into_iter().map(Into::into).collect(),
+        keys: proof.items.iter().map(|(key, _)| key.clone().into()).collect(),
+    };
+    let result = runner.call_raw(addr, call.abi_encode());
+    let decoded = VerifyKeysCall::abi_decode_returns(&result, true).unwrap();
+
+    for (value, (_, expected)) in decoded._0.into_iter().zip(proof.items) {
+        prop_assert_eq!(value.value.to_vec(), expected.unwrap_or_default());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "strategies")]
+proptest! {
+    /// Every proven key, present or absent, must resolve to its value in the trie.
+    #[test]
+    fn test_arb_substrate_trie_proof(
+        proof in crate::strategies::arb_substrate_trie_proof::<LayoutV0<KeccakHasher>>(),
+    ) {
+        verify_arb_substrate_trie_proof(proof)?;
+    }
+
+    /// As above, with values over 32 bytes stored hashed.
+    #[test]
+    fn test_arb_substrate_trie_proof_v1(
+        proof in crate::strategies::arb_substrate_trie_proof::<LayoutV1<KeccakHasher>>(),
+    ) {
+        verify_arb_substrate_trie_proof(proof)?;
+    }
 }
 
 #[test]
@@ -383,29 +721,37 @@ fn test_merkle_patricia_trie_ethereum_verify_transaction_trie_single_node() {
     );
 }
 
+/// Multi-node transaction trie proof for the transaction at index 200.
+pub(crate) fn ethereum_transaction_proof_data() -> ([u8; 32], Vec<Vec<u8>>, Vec<u8>, Vec<u8>) {
+    let root = hex!("ac39df3a470f95659f9f6f30c4de252479ddd4e6083ba7a7be72d2505b4062e2");
+    let proof = vec![
+        hex!("f90131a0abd3b92264de818dd5c44b3212ee3d20de2478ca6a080d59b0f7eadc165aea33a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a06bae3f278b07352be6e823e9c9584d2a8ba01e000cc7653b5b1d213888b841548080808080808080").to_vec(),
+        hex!("f871a036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da060c25b7b04d23c401c874963020e932bcafdef5b9b5c4f25394e2c3bba644feca06cf2dabf824eee4758b57a77612e1f7a0d483e3656d9a76ea7b11a70a50dd4008080808080808080808080808080").to_vec(),
+        hex!("f8b1a0cb8fc8ea7d198bd1b6fb48a51e17932ad8333ef0b57b17326fbe3c4f6abf231ea018c89cbf38ef1aa86fc642cfc5eae17f49aef97c493c5ada614743d630de32fba018c89cbf38ef1aa86fc642cfc5eae17f49aef97c493c5ada614743d630de32fba018c89cbf38ef1aa86fc642cfc5eae17f49aef97c493c5ada614743d630de32fba0951955209df36420e52fc0961cba8318a288676262651f9e7cdafd0cf177c1e5808080808080808080808080").to_vec(),
+        hex!("f90211a03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aa80").to_vec(),
+        hex!("f90211a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd180").to_vec(),
+        hex!("f90211a036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036d80").to_vec(),
+        hex!("f89920b89601f89301808080808080f847f84580f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000080a08c7939f0e613736150a05565fcddda959b22c44ddac6c6aed8ec59e1462a0498a0166d30e3763829d64fca3d38601e65ba6f0e94f7e3c544381ae5e9e9b12dacd0").to_vec(),
+    ];
+    let key = hex!("8232c8").to_vec();
+    let value = hex!("01f89301808080808080f847f84580f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000080a08c7939f0e613736150a05565fcddda959b22c44ddac6c6aed8ec59e1462a0498a0166d30e3763829d64fca3d38601e65ba6f0e94f7e3c544381ae5e9e9b12dacd0").to_vec();
+
+    (root, proof, key, value)
+}
+
 #[test]
 fn test_merkle_patricia_trie_ethereum_verify_transaction_trie_multi_node() {
+    let (root, proof, key, value) = ethereum_transaction_proof_data();
     let (mut runner, addr) = setup();
 
     let call = VerifyEthereumCall {
-        root: FixedBytes(hex!("ac39df3a470f95659f9f6f30c4de252479ddd4e6083ba7a7be72d2505b4062e2")),
-        proof: vec![
-            hex!("f90131a0abd3b92264de818dd5c44b3212ee3d20de2478ca6a080d59b0f7eadc165aea33a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a06bae3f278b07352be6e823e9c9584d2a8ba01e000cc7653b5b1d213888b841548080808080808080").to_vec().into(),
-            hex!("f871a036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da060c25b7b04d23c401c874963020e932bcafdef5b9b5c4f25394e2c3bba644feca06cf2dabf824eee4758b57a77612e1f7a0d483e3656d9a76ea7b11a70a50dd4008080808080808080808080808080").to_vec().into(),
-            hex!("f8b1a0cb8fc8ea7d198bd1b6fb48a51e17932ad8333ef0b57b17326fbe3c4f6abf231ea018c89cbf38ef1aa86fc642cfc5eae17f49aef97c493c5ada614743d630de32fba018c89cbf38ef1aa86fc642cfc5eae17f49aef97c493c5ada614743d630de32fba018c89cbf38ef1aa86fc642cfc5eae17f49aef97c493c5ada614743d630de32fba0951955209df36420e52fc0961cba8318a288676262651f9e7cdafd0cf177c1e5808080808080808080808080").to_vec().into(),
-            hex!("f90211a03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aaa03f4a0248cd0ff7ad4496f72fde07d31a0d46d7986af6505f0e4e85b72b3401aa80").to_vec().into(),
-            hex!("f90211a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd1a014dd1fc275045e27cee26d6ec8395aa68681155e524550de255580f4181e7cd180").to_vec().into(),
-            hex!("f90211a036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036da036218dd16b29d804118d9bb961896fd3eb036e028af02d3638937cdcaa65036d80").to_vec().into(),
-            hex!("f89920b89601f89301808080808080f847f84580f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000080a08c7939f0e613736150a05565fcddda959b22c44ddac6c6aed8ec59e1462a0498a0166d30e3763829d64fca3d38601e65ba6f0e94f7e3c544381ae5e9e9b12dacd0").to_vec().into(),
-        ],
-        keys: vec![hex!("8232c8").to_vec().into()],
+        root: FixedBytes(root),
+        proof: proof.into_iter().map(Into::into).collect(),
+        keys: vec![key.into()],
     };
     let result = runner.call_raw(addr, call.abi_encode());
     let decoded = VerifyEthereumCall::abi_decode_returns(&result, true).unwrap();
-    assert_eq!(
-        decoded._0[0].value.to_vec(),
-        hex!("01f89301808080808080f847f84580f842a00000000000000000000000000000000000000000000000000000000000000000a0000000000000000000000000000000000000000000000000000000000000000080a08c7939f0e613736150a05565fcddda959b22c44ddac6c6aed8ec59e1462a0498a0166d30e3763829d64fca3d38601e65ba6f0e94f7e3c544381ae5e9e9b12dacd0").to_vec()
-    );
+    assert_eq!(decoded._0[0].value.to_vec(), value);
 }
 
 #[test]
@@ -427,24 +773,280 @@ fn test_merkle_patricia_trie_ethereum_verify_state_trie_single_node() {
     );
 }
 
+/// Multi-node state trie proof for a single account.
+pub(crate) fn ethereum_state_proof_data() -> ([u8; 32], Vec<Vec<u8>>, Vec<u8>, Vec<u8>) {
+    let root = hex!("4dc3e58e944d713c36c6b9cc58df023b3e578093de16e175faefa8f91727ca6e");
+    let proof = vec![
+        hex!("f90211a07466452b9c24acc76f0c9a0a4d43f5b362a031626eb6a0d7e7409c9c2fe1ecdda00bc98d4eaa34347ecb42d4d716161612692c2ac599cf3c4f5eade18fbb07da4ca00146ab246da36aec011dcdf8fd4c6a505ec7caca75441268c7eab80e9aa96767a038a41d42f1edccf59f2a1d6e7887ab1f3dfcd0a26f3be309ee237ed4aecd9d38a05f542a7ffff85163015c7cfc8c7d947ac98114f1bc576ab74ec663dd97d9596fa0208cb7384b248a341c22ef52d1882f6045345c6435c38a5b4d382e7a02c53f48a086b590086c7e7738c59cd0bba1a136dc42cc099fa9ae8af77abaa76fa1f3f503a0ab69ef5e7d461a547675de48c30be16f2d297509f6d005325365cbebe8735104a0896573c4595ea56992cb4237091ce8f00a73988f80506ef7bde78de9cfddbfa9a06d78ae475034b4aec9afef58c3f93e997f2c50f2a4948a7214b0295c5ae1776ea0763c0ec3ea13b7cbfe139cf8a3cf76e75026b2d42854bf822a47a0497dabf679a028fd50ebf9eed4e9a0969a73682ea615cb1134510f80aa057c60acf657a13a05a00c9f1e12244dabf2db619f0ce1098dd6d19f7c9dd1b17da1ffd02b0e0f3d4d7ea0aa2a772e989b23bd7e2eba714f153031c79c03cb835539fc56debc7669b64148a07f6544adbc5e30eca006a050384d85df7a510bac66dde8c32b7741486b319610a0c859cc09be23308083a16f96c19dffd9b48770b715c150220a35e07ff5ea716b80").to_vec(),
+        hex!("f90171a02ffa31221e3db9f56751599b181b16cd0489d9870f58a481ee1398d6991a6ed3a0af7f7b8a8aa219ebd9e9562bd3f917c47f7205bb0f29bdb064c63e51f8e14ee680a0fc0482eb10e5eccc57013233746a95072c2d80746898c30a23fc43c52eabddf5a0985b92a5617ee65b05be517cbbeb1c5598e5479bdb45107552b80d339c6c23eba0984d7ace51d61b40d4336aec39c867fc5db4566036ef0e6225df604dd93a6538a068ff593d5fc203763242dc6b95e559472f8d43ceed1a4fececb11a0eb6ef1070a0d7a609f017d3641ff18327916212ebf21d6ae93b3ffe09ae6fc5e1160c7f571f80a0d62e9137db3e883d506c3927310d0100e624b5befe180830f029c99859451230a0ce9a78f4d5abb17cfe7c189a83524a479bd8aed870cc7608c3ede431cdd214cd8080a0f58d48fa8ca7b152aa825128a173c6061b6cabe9143473e246f6d8732f59cc3880a030a23d0cd92c346ce27019e17ddbf8651695cd3fd6350f0f7862f915269a57ff80").to_vec(),
+        hex!("f8518080808080808080a0fed0c7841e83453b78135ca2a36e8fb5d8c5fbb5883746f3f93054e42205e7e880808080a0711f6aa6ad472844f3e563a1c3ff5777c4e2c3b3126b42fa4eb4d115546116c5808080").to_vec(),
+        hex!("f8689f30c7b7ae81a58eb98d9c78de4a1fd7fd9535fc953ed2be602daaa41767312ab846f8448080a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").to_vec(),
+    ];
+    let key = hex!("5380c7b7ae81a58eb98d9c78de4a1fd7fd9535fc953ed2be602daaa41767312a").to_vec();
+    let value = hex!("f8448080a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").to_vec();
+
+    (root, proof, key, value)
+}
+
 #[test]
 fn test_merkle_patricia_trie_ethereum_verify_state_trie_multi_node() {
+    let (root, proof, key, value) = ethereum_state_proof_data();
     let (mut runner, addr) = setup();
 
     let call = VerifyEthereumCall {
-        root: FixedBytes(hex!("4dc3e58e944d713c36c6b9cc58df023b3e578093de16e175faefa8f91727ca6e")),
-        proof: vec![
-            hex!("f90211a07466452b9c24acc76f0c9a0a4d43f5b362a031626eb6a0d7e7409c9c2fe1ecdda00bc98d4eaa34347ecb42d4d716161612692c2ac599cf3c4f5eade18fbb07da4ca00146ab246da36aec011dcdf8fd4c6a505ec7caca75441268c7eab80e9aa96767a038a41d42f1edccf59f2a1d6e7887ab1f3dfcd0a26f3be309ee237ed4aecd9d38a05f542a7ffff85163015c7cfc8c7d947ac98114f1bc576ab74ec663dd97d9596fa0208cb7384b248a341c22ef52d1882f6045345c6435c38a5b4d382e7a02c53f48a086b590086c7e7738c59cd0bba1a136dc42cc099fa9ae8af77abaa76fa1f3f503a0ab69ef5e7d461a547675de48c30be16f2d297509f6d005325365cbebe8735104a0896573c4595ea56992cb4237091ce8f00a73988f80506ef7bde78de9cfddbfa9a06d78ae475034b4aec9afef58c3f93e997f2c50f2a4948a7214b0295c5ae1776ea0763c0ec3ea13b7cbfe139cf8a3cf76e75026b2d42854bf822a47a0497dabf679a028fd50ebf9eed4e9a0969a73682ea615cb1134510f80aa057c60acf657a13a05a00c9f1e12244dabf2db619f0ce1098dd6d19f7c9dd1b17da1ffd02b0e0f3d4d7ea0aa2a772e989b23bd7e2eba714f153031c79c03cb835539fc56debc7669b64148a07f6544adbc5e30eca006a050384d85df7a510bac66dde8c32b7741486b319610a0c859cc09be23308083a16f96c19dffd9b48770b715c150220a35e07ff5ea716b80").to_vec().into(),
-            hex!("f90171a02ffa31221e3db9f56751599b181b16cd0489d9870f58a481ee1398d6991a6ed3a0af7f7b8a8aa219ebd9e9562bd3f917c47f7205bb0f29bdb064c63e51f8e14ee680a0fc0482eb10e5eccc57013233746a95072c2d80746898c30a23fc43c52eabddf5a0985b92a5617ee65b05be517cbbeb1c5598e5479bdb45107552b80d339c6c23eba0984d7ace51d61b40d4336aec39c867fc5db4566036ef0e6225df604dd93a6538a068ff593d5fc203763242dc6b95e559472f8d43ceed1a4fececb11a0eb6ef1070a0d7a609f017d3641ff18327916212ebf21d6ae93b3ffe09ae6fc5e1160c7f571f80a0d62e9137db3e883d506c3927310d0100e624b5befe180830f029c99859451230a0ce9a78f4d5abb17cfe7c189a83524a479bd8aed870cc7608c3ede431cdd214cd8080a0f58d48fa8ca7b152aa825128a173c6061b6cabe9143473e246f6d8732f59cc3880a030a23d0cd92c346ce27019e17ddbf8651695cd3fd6350f0f7862f915269a57ff80").to_vec().into(),
-            hex!("f8518080808080808080a0fed0c7841e83453b78135ca2a36e8fb5d8c5fbb5883746f3f93054e42205e7e880808080a0711f6aa6ad472844f3e563a1c3ff5777c4e2c3b3126b42fa4eb4d115546116c5808080").to_vec().into(),
-            hex!("f8689f30c7b7ae81a58eb98d9c78de4a1fd7fd9535fc953ed2be602daaa41767312ab846f8448080a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").to_vec().into(),
-        ],
-        keys: vec![hex!("5380c7b7ae81a58eb98d9c78de4a1fd7fd9535fc953ed2be602daaa41767312a").to_vec().into()],
+        root: FixedBytes(root),
+        proof: proof.into_iter().map(Into::into).collect(),
+        keys: vec![key.into()],
     };
     let result = runner.call_raw(addr, call.abi_encode());
     let decoded = VerifyEthereumCall::abi_decode_returns(&result, true).unwrap();
-    assert_eq!(
-        decoded._0[0].value.to_vec(),
-        hex!("f8448080a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").to_vec()
-    );
+    assert_eq!(decoded._0[0].value.to_vec(), value);
+}
+
+/// Keys are compared with the whole partial key of a leaf, so an absent key must not verify with
+/// the value of a leaf it only differs from in the leaf's first nibble, as the transaction trie key
+/// rlp(2) does from the leaf holding rlp(1). A branch value of a single 0x80 byte, which encodes
+/// as `0x8180`, must not be mistaken for the empty string of a branch without a value.
+#[test]
+fn test_ethereum_proof_of_keys_sharing_a_leaf_path() {
+    let entries = vec![
+        (alloy_rlp::encode(0u64), vec![0; 40]),
+        (alloy_rlp::encode(1u64), vec![1; 40]),
+        (vec![0x12], vec![0x80]),
+        (vec![0x12, 0x34], vec![2; 40]),
+    ];
+    let keys = entries
+        .iter()
+        .map(|(key, _)| key.clone())
+        .chain([alloy_rlp::encode(2u64), vec![0x81], vec![0x12, 0x35]])
+        .collect::<Vec<_>>();
+    let TrieProof { root, proof, items } = generate_trie_proof::<EthereumLayout>(&entries, &keys);
+    let (mut runner, addr) = setup();
+
+    let call = VerifyEthereumCall {
+        root: FixedBytes(root.into()),
+        proof: proof.into_iter().map(Into::into).collect(),
+        keys: keys.into_iter().map(Into::into).collect(),
+    };
+    let result = runner.call_raw(addr, call.abi_encode());
+    let decoded = VerifyEthereumCall::abi_decode_returns(&result, true).unwrap();
+    for (value, (key, expected)) in decoded._0.into_iter().zip(items) {
+        assert_eq!(value.value.to_vec(), expected.unwrap_or_default(), "key={}", hex::encode(key));
+    }
+}
+
+/// Nodes whose encoding is shorter than a hash are embedded in their parent, and the verifier
+/// follows them there, from branches and extensions alike.
+#[test]
+fn test_ethereum_proof_of_embedded_nodes() {
+    let (mut runner, addr) = setup();
+    let mut verify = |entries: &[(Vec<u8>, Vec<u8>)]| {
+        let keys = entries
+            .iter()
+            .map(|(key, _)| key.clone())
+            .chain([vec![0x13], vec![0x30]])
+            .collect::<Vec<_>>();
+        let TrieProof { root, proof, items } =
+            generate_trie_proof::<EthereumLayout>(entries, &keys);
+        // every node below the root is embedded in it
+        assert_eq!(proof.len(), 1);
+        let call = VerifyEthereumCall {
+            root: FixedBytes(root.into()),
+            proof: proof.into_iter().map(Into::into).collect(),
+            keys: keys.into_iter().map(Into::into).collect(),
+        };
+        let decoded = runner.call_sol(addr, call).unwrap();
+        for (value, (key, expected)) in decoded._0.into_iter().zip(items) {
+            assert_eq!(value.value.to_vec(), expected.unwrap_or_default(), "key={key:?}");
+        }
+    };
+
+    // a branch holding two embedded leaves
+    verify(&[(vec![0x10], vec![1]), (vec![0x20], vec![2])]);
+    // an extension to a branch embedded along with its leaves
+    verify(&[(vec![0x11], vec![1]), (vec![0x12], vec![2])]);
+}
+
+proptest! {
+    /// Proofs generated with `EthereumLayout` verify. Keys are either 32 bytes, as in the secure
+    /// tries holding Ethereum's state and storage, or a few bytes, which along with short values
+    /// leave nodes short enough to be embedded in their parent.
+    #[test]
+    fn test_generated_ethereum_proof(
+        entries in prop::collection::btree_map(
+            prop_oneof![
+                prop::collection::vec(any::<u8>(), 32),
+                prop::collection::vec(any::<u8>(), 1..4),
+            ],
+            prop::collection::vec(any::<u8>(), 1..100),
+            1..64,
+        ),
+        absent in prop::collection::vec(prop::collection::vec(any::<u8>(), 1..4), 0..4),
+    ) {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        let keys = entries
+            .iter()
+            .map(|(key, _)| key.clone())
+            .chain(absent)
+            .collect::<Vec<_>>();
+        let TrieProof { root, proof, items } =
+            generate_trie_proof::<EthereumLayout>(&entries, &keys);
+        let (mut runner, addr) = setup();
+
+        let call = VerifyEthereumCall {
+            root: FixedBytes(root.into()),
+            proof: proof.into_iter().map(Into::into).collect(),
+            keys: keys.into_iter().map(Into::into).collect(),
+        };
+        let result = runner.call_raw(addr, call.abi_encode());
+        let decoded = VerifyEthereumCall::abi_decode_returns(&result, true).unwrap();
+        for (value, (_, expected)) in decoded._0.into_iter().zip(items) {
+            prop_assert_eq!(value.value.to_vec(), expected.unwrap_or_default());
+        }
+    }
+}
+
+/// Order in which proof nodes are handed to the verifier. `TrieDB.get` scans the node list
+/// linearly, so the position of each node affects the cost of looking it up.
+#[derive(Clone, Copy, Debug)]
+enum ProofOrder {
+    RootFirst,
+    RootLast,
+}
+
+impl ProofOrder {
+    fn apply(self, mut proof: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        if let ProofOrder::RootLast = self {
+            proof.reverse();
+        }
+        proof
+    }
+}
+
+/// The verifier a benchmarked proof is handed to.
+#[derive(Clone, Copy, Debug)]
+enum Verifier {
+    Substrate,
+    Ethereum,
+}
+
+fn bench_trie_proof<L: TrieLayout>(
+    runner: &mut EvmRunner,
+    contract: alloy_primitives::Address,
+    verifier: Verifier,
+    layout: &str,
+    trie_size: usize,
+    keys_per_proof: usize,
+    value_size: usize,
+) {
+    let entries = (0..trie_size)
+        .map(|i| {
+            let key = alloy_primitives::keccak256((i as u32).to_le_bytes()).to_vec();
+            (key, vec![i as u8; value_size])
+        })
+        .collect::<Vec<_>>();
+    // spread the proven keys across the trie
+    let keys = entries
+        .iter()
+        .step_by(trie_size / keys_per_proof)
+        .take(keys_per_proof)
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+
+    let TrieProof { root, proof, items } = generate_trie_proof::<L>(&entries, &keys);
+
+    for order in [ProofOrder::RootFirst, ProofOrder::RootLast] {
+        let proof = order.apply(proof.clone());
+        let root = FixedBytes::from_slice(root.as_ref());
+        let proof_nodes = proof.iter().cloned().map(Into::into).collect::<Vec<_>>();
+        let proven_keys = keys.iter().cloned().map(Into::into).collect::<Vec<_>>();
+
+        let (values, gas) = match verifier {
+            Verifier::Substrate => {
+                let call = VerifyKeysCall { root, proof: proof_nodes, keys: proven_keys };
+                let (result, gas) = runner.call_with_gas(contract, call.abi_encode());
+                (VerifyKeysCall::abi_decode_returns(&result, true).unwrap()._0, gas)
+            },
+            Verifier::Ethereum => {
+                let call = VerifyEthereumCall { root, proof: proof_nodes, keys: proven_keys };
+                let (result, gas) = runner.call_with_gas(contract, call.abi_encode());
+                (VerifyEthereumCall::abi_decode_returns(&result, true).unwrap()._0, gas)
+            },
+        };
+        assert_eq!(values.len(), items.len());
+        for (value, (key, expected)) in values.iter().zip(&items) {
+            assert_eq!(value.value.to_vec(), expected.clone().unwrap(), "key={}", hex::encode(key));
+        }
+
+        println!(
+            "layout={layout}  entries={:>4}  keys={:>2}  value_size={:>2}  order={:<9}  proof_nodes={:>3}  gas={:>8}",
+            trie_size,
+            keys_per_proof,
+            value_size,
+            format!("{order:?}"),
+            proof.len(),
+            gas
+        );
+    }
+}
+
+/// Gas of Substrate proofs across trie sizes, proven keys and value sizes. Run with `--ignored
+/// --nocapture` to print the measurements.
+#[test]
+#[ignore]
+fn test_merkle_patricia_substrate_gas_benchmark() {
+    let (mut runner, contract) = setup();
+
+    // values of 4 bytes are always inlined, values of 64 bytes are hashed under LayoutV1
+    for value_size in [4usize, 64] {
+        for trie_size in [16usize, 256, 1024, 4096] {
+            for keys_per_proof in [1usize, 8, 16] {
+                bench_trie_proof::<LayoutV0<KeccakHasher>>(
+                    &mut runner,
+                    contract,
+                    Verifier::Substrate,
+                    "v0",
+                    trie_size,
+                    keys_per_proof,
+                    value_size,
+                );
+                bench_trie_proof::<LayoutV1<KeccakHasher>>(
+                    &mut runner,
+                    contract,
+                    Verifier::Substrate,
+                    "v1",
+                    trie_size,
+                    keys_per_proof,
+                    value_size,
+                );
+            }
+        }
+    }
+}
+
+/// Gas of Ethereum proofs across trie sizes, proven keys and value sizes, in tries keyed by 32
+/// byte hashes as the state and storage tries are. Run with `--ignored --nocapture` to print the
+/// measurements.
+#[test]
+#[ignore]
+fn test_merkle_patricia_ethereum_gas_benchmark() {
+    let (mut runner, contract) = setup();
+
+    // leaves keep partial keys of nearly 32 bytes at these sizes, so none is embedded in its
+    // parent
+    for value_size in [4usize, 64] {
+        for trie_size in [16usize, 256, 1024, 4096] {
+            for keys_per_proof in [1usize, 8, 16] {
+                bench_trie_proof::<EthereumLayout>(
+                    &mut runner,
+                    contract,
+                    Verifier::Ethereum,
+                    "eth",
+                    trie_size,
+                    keys_per_proof,
+                    value_size,
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,43 @@
+use crate::{multi_proof_utils::Leaf, MergeKeccak, NumberHash};
+use ckb_merkle_mountain_range::{util::MemStore, MMR};
+use primitive_types::H256;
+
+/// An MMR root and a proof for some of its leaves, in the format expected by the Solidity
+/// MerkleMountainRange verifier.
+#[derive(Debug, Clone)]
+pub struct MmrProof {
+    pub root: H256,
+    pub leaf_count: u64,
+    pub proof_items: Vec<H256>,
+    pub leaves: Vec<Leaf>,
+}
+
+/// Build an MMR of `leaf_count` leaves and a proof for the leaves at the 0-based `indices`. Leaf
+/// `i` is the keccak256 of `i` in little-endian, see [`NumberHash`].
+pub fn generate_mmr_proof(leaf_count: u32, indices: &[u32]) -> MmrProof {
+    let mut indices = indices.to_vec();
+    indices.sort();
+    indices.dedup();
+
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeKeccak, _>::new(0, &store);
+    let positions: Vec<u64> =
+        (0..leaf_count).map(|i| mmr.push(NumberHash::from(i)).unwrap()).collect();
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr
+        .gen_proof(indices.iter().map(|&i| positions[i as usize]).collect())
+        .expect("gen proof");
+    mmr.commit().expect("commit changes");
+
+    let leaves = indices
+        .iter()
+        .map(|&i| Leaf { hash: H256::from_slice(&NumberHash::from(i).0), index: i as usize })
+        .collect();
+
+    MmrProof {
+        root: H256::from_slice(&root.0),
+        leaf_count: leaf_count as u64,
+        proof_items: proof.proof_items().iter().map(|item| H256::from_slice(&item.0)).collect(),
+        leaves,
+    }
+}
@@ -1,6 +1,7 @@
 use crate::Keccak256;
+use alloy_primitives::keccak256;
 use primitive_types::H256;
-use rs_merkle::MerkleProof;
+use rs_merkle::{MerkleProof, MerkleTree};
 
 /// A leaf in the merkle tree with its 0-based index and hash value.
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -18,6 +19,7 @@ pub struct RsMerkleProof<'a> {
 }
 
 /// The converted proof ready for Solidity consumption.
+#[derive(Debug, Clone)]
 pub struct SolidityProof {
     pub proof_hashes: Vec<H256>,
     pub leaves: Vec<Leaf>,
@@ -38,3 +40,37 @@ impl<'a> From<RsMerkleProof<'a>> for SolidityProof {
         SolidityProof { proof_hashes, leaves }
     }
 }
+
+/// A tree root and a multi proof for some of its leaves.
+#[derive(Debug, Clone)]
+pub struct MultiProof {
+    pub root: H256,
+    pub num_leaves: usize,
+    pub proof: SolidityProof,
+}
+
+/// The leaf hashes of the trees built by [`generate_multi_proof`], the keccak256 of each 0-based
+/// leaf index in little-endian.
+pub fn leaf_hashes(num_leaves: usize) -> Vec<[u8; 32]> {
+    (0..num_leaves).map(|i| keccak256((i as u32).to_le_bytes()).0).collect()
+}
+
+/// Build a tree of `num_leaves` leaves and a multi proof for the leaves at `indices`.
+pub fn generate_multi_proof(num_leaves: usize, indices: &[usize]) -> MultiProof {
+    let leaf_hashes = leaf_hashes(num_leaves);
+    let tree = MerkleTree::<Keccak256>::from_leaves(&leaf_hashes);
+
+    let mut indices = indices.to_vec();
+    indices.sort();
+    indices.dedup();
+
+    let proof = tree.proof(&indices);
+    let leaves_to_prove: Vec<[u8; 32]> = indices.iter().map(|&i| leaf_hashes[i]).collect();
+    let proof = SolidityProof::from(RsMerkleProof {
+        proof: &proof,
+        leaf_indices: &indices,
+        leaf_hashes: &leaves_to_prove,
+    });
+
+    MultiProof { root: H256(tree.root().unwrap()), num_leaves, proof }
+}
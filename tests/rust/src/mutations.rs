@@ -0,0 +1,288 @@
+use crate::{
+    mmr_utils::MmrProof,
+    multi_proof_utils::{Leaf, MultiProof},
+    trie_utils::TrieProof,
+};
+use primitive_types::H256;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::ops::RangeInclusive;
+
+/// A class of corruption applied to an otherwise valid proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutation {
+    /// Flip a single bit of a leaf hash or proof element.
+    BitFlip,
+    /// Drop the last proof element.
+    Truncate,
+    /// Append a random element to the proof.
+    AppendJunk,
+    /// Swap two distinct proof elements.
+    Reorder,
+    /// Prove one of the leaves twice.
+    DuplicateLeaf,
+    /// Swap the hash of a leaf with a proof element.
+    SwapLeafAndProof,
+    /// Claim one leaf more or one leaf fewer than the tree has.
+    LeafCountOffByOne,
+}
+
+impl Mutation {
+    pub const ALL: [Mutation; 7] = [
+        Mutation::BitFlip,
+        Mutation::Truncate,
+        Mutation::AppendJunk,
+        Mutation::Reorder,
+        Mutation::DuplicateLeaf,
+        Mutation::SwapLeafAndProof,
+        Mutation::LeafCountOffByOne,
+    ];
+
+    /// Every mutation besides those in `excluded`.
+    pub fn all_except(excluded: &[Mutation]) -> Vec<Mutation> {
+        Self::ALL.into_iter().filter(|mutation| !excluded.contains(mutation)).collect()
+    }
+}
+
+/// Mutations the MMR verifier wrongly accepts for some proofs: it ignores unconsumed proof items,
+/// doesn't bind the leaf count to the root when the proven leaves don't reach the last peaks, and
+/// hashes a leaf that is a peak on its own to itself however often it is proven. They fail the
+/// ignored `test_small_mmr_proofs_reject_every_mutation` until the verifier is fixed, and are left
+/// out of the other mutation tests so those keep checking the rest.
+pub const MMR_WEAKNESSES: &[Mutation] =
+    &[Mutation::AppendJunk, Mutation::DuplicateLeaf, Mutation::LeafCountOffByOne];
+
+/// Mutations the multi proof verifier wrongly accepts for some proofs: it ignores unconsumed
+/// proof items and only uses the leaf count where the proven leaves reach the right edge of the
+/// tree. They fail the ignored `test_small_multi_proofs_reject_every_mutation` until the verifier
+/// is fixed, and are left out of the other mutation tests so those keep checking the rest.
+pub const MULTI_PROOF_WEAKNESSES: &[Mutation] =
+    &[Mutation::AppendJunk, Mutation::LeafCountOffByOne];
+
+/// Mutations the trie verifiers are known to accept. Trie proofs are an unordered set of nodes
+/// looked up by hash, so reordering them or adding a node nothing refers to leaves the proven
+/// values unchanged, as does proving a key twice.
+pub const TRIE_KNOWN_ACCEPTED: &[Mutation] =
+    &[Mutation::AppendJunk, Mutation::Reorder, Mutation::DuplicateLeaf];
+
+/// A proof that can be corrupted by a [`Mutation`].
+pub trait Mutate: Clone {
+    /// Returns a copy of this proof corrupted by `mutation`, using `seed` to choose which part of
+    /// it to corrupt, or `None` if the mutation does not apply to it.
+    fn mutate(&self, mutation: Mutation, seed: u64) -> Option<Self>;
+}
+
+/// The outcome of applying a set of mutations to a proof.
+#[derive(Debug, Default)]
+pub struct MutationReport {
+    /// Mutations that applied to the proof.
+    pub applied: Vec<Mutation>,
+    /// Mutations whose corrupted proof still verified against the original root, although the
+    /// verifier isn't known to accept them.
+    pub accepted: Vec<Mutation>,
+    /// Mutations the verifier is known to accept whose corrupted proof still verified.
+    pub known_accepted: Vec<Mutation>,
+}
+
+/// Apply each of `mutations` to `proof` and report those for which `verifies` still accepts the
+/// corrupted proof, separating the ones in `known_accepted` from the rest.
+pub fn check_mutations<P: Mutate>(
+    proof: &P,
+    mutations: &[Mutation],
+    known_accepted: &[Mutation],
+    seed: u64,
+    mut verifies: impl FnMut(&P) -> bool,
+) -> MutationReport {
+    let mut report = MutationReport::default();
+    for &mutation in mutations {
+        let Some(mutated) = proof.mutate(mutation, seed) else { continue };
+        report.applied.push(mutation);
+        if !verifies(&mutated) {
+            continue
+        }
+        if known_accepted.contains(&mutation) {
+            report.known_accepted.push(mutation);
+        } else {
+            report.accepted.push(mutation);
+        }
+    }
+    report
+}
+
+/// Apply each of `mutations` under a few seeds to proofs of the first leaf, of the last leaf and of
+/// every leaf of trees of each of `sizes` leaves, asserting that none of the corrupted proofs
+/// verifies. `prove` builds the proof of the given leaves of a tree of the given size, and
+/// `verifies` checks a corrupted proof against the proof it was made from.
+pub fn assert_small_proofs_reject<P: Mutate>(
+    sizes: RangeInclusive<usize>,
+    mutations: &[Mutation],
+    mut prove: impl FnMut(usize, &[usize]) -> P,
+    mut verifies: impl FnMut(&P, &P) -> bool,
+) {
+    for size in sizes {
+        for indices in [vec![0], vec![size - 1], (0..size).collect()] {
+            let proof = prove(size, &indices);
+            for seed in 0..4 {
+                let report = check_mutations(&proof, mutations, &[], seed, |mutated| {
+                    verifies(&proof, mutated)
+                });
+                assert!(
+                    report.accepted.is_empty(),
+                    "mutations verified for {size} leaves proving {indices:?}: {:?}",
+                    report.accepted
+                );
+            }
+        }
+    }
+}
+
+/// Corrupt the leaves, proof items or leaf count of a proof over 32-byte hashes, returning false
+/// if the mutation does not apply.
+fn mutate_hashes(
+    leaves: &mut Vec<Leaf>,
+    items: &mut Vec<H256>,
+    leaf_count: &mut u64,
+    mutation: Mutation,
+    rng: &mut StdRng,
+) -> bool {
+    match mutation {
+        Mutation::BitFlip => {
+            if leaves.is_empty() && items.is_empty() {
+                return false
+            }
+            let target = rng.gen_range(0..leaves.len() + items.len());
+            let hash = match target.checked_sub(leaves.len()) {
+                Some(i) => &mut items[i],
+                None => &mut leaves[target].hash,
+            };
+            let bit = rng.gen_range(0..256);
+            hash.0[bit / 8] ^= 1 << (bit % 8);
+        },
+        Mutation::Truncate =>
+            if items.pop().is_none() {
+                return false
+            },
+        Mutation::AppendJunk => items.push(H256(rng.gen())),
+        Mutation::Reorder => {
+            if items.len() < 2 {
+                return false
+            }
+            let i = rng.gen_range(0..items.len());
+            let Some(j) = (0..items.len()).find(|&j| items[j] != items[i]) else { return false };
+            items.swap(i, j);
+        },
+        Mutation::DuplicateLeaf => {
+            if leaves.is_empty() {
+                return false
+            }
+            let i = rng.gen_range(0..leaves.len());
+            leaves.insert(i + 1, leaves[i].clone());
+        },
+        Mutation::SwapLeafAndProof => {
+            if leaves.is_empty() || items.is_empty() {
+                return false
+            }
+            let leaf = rng.gen_range(0..leaves.len());
+            let item = rng.gen_range(0..items.len());
+            std::mem::swap(&mut leaves[leaf].hash, &mut items[item]);
+        },
+        Mutation::LeafCountOffByOne =>
+            if *leaf_count > 1 && rng.gen() {
+                *leaf_count -= 1;
+            } else {
+                *leaf_count += 1;
+            },
+    }
+    true
+}
+
+impl Mutate for MultiProof {
+    fn mutate(&self, mutation: Mutation, seed: u64) -> Option<Self> {
+        let mut mutated = self.clone();
+        let mut num_leaves = self.num_leaves as u64;
+        let applied = mutate_hashes(
+            &mut mutated.proof.leaves,
+            &mut mutated.proof.proof_hashes,
+            &mut num_leaves,
+            mutation,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        mutated.num_leaves = num_leaves as usize;
+        applied.then_some(mutated)
+    }
+}
+
+impl Mutate for MmrProof {
+    fn mutate(&self, mutation: Mutation, seed: u64) -> Option<Self> {
+        let mut mutated = self.clone();
+        let applied = mutate_hashes(
+            &mut mutated.leaves,
+            &mut mutated.proof_items,
+            &mut mutated.leaf_count,
+            mutation,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        applied.then_some(mutated)
+    }
+}
+
+/// Trie proofs have no leaf count, and their leaves are the values of the proven keys, some of
+/// which are proof nodes themselves under `LayoutV1`.
+impl<H: Clone> Mutate for TrieProof<H> {
+    fn mutate(&self, mutation: Mutation, seed: u64) -> Option<Self> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut mutated = self.clone();
+        match mutation {
+            Mutation::BitFlip => {
+                if self.proof.is_empty() {
+                    return None
+                }
+                let node = &mut mutated.proof[rng.gen_range(0..self.proof.len())];
+                let bit = rng.gen_range(0..node.len() * 8);
+                node[bit / 8] ^= 1 << (bit % 8);
+            },
+            Mutation::Truncate => {
+                mutated.proof.pop()?;
+            },
+            Mutation::AppendJunk => {
+                let len = rng.gen_range(1..=64);
+                mutated.proof.push((0..len).map(|_| rng.gen()).collect());
+            },
+            Mutation::Reorder => {
+                if self.proof.len() < 2 {
+                    return None
+                }
+                let i = rng.gen_range(0..self.proof.len());
+                let j = (0..self.proof.len()).find(|&j| self.proof[j] != self.proof[i])?;
+                mutated.proof.swap(i, j);
+            },
+            Mutation::DuplicateLeaf => {
+                if self.items.is_empty() {
+                    return None
+                }
+                let i = rng.gen_range(0..self.items.len());
+                mutated.items.insert(i + 1, self.items[i].clone());
+            },
+            Mutation::SwapLeafAndProof => {
+                // a value swapped with an identical node, such as its own value node, is unchanged
+                let present = (0..self.items.len())
+                    .filter(|&i| self.items[i].1.is_some())
+                    .collect::<Vec<_>>();
+                if present.is_empty() {
+                    return None
+                }
+                let item = present[rng.gen_range(0..present.len())];
+                let value = self.items[item].1.clone().unwrap();
+                let nodes =
+                    (0..self.proof.len()).filter(|&i| self.proof[i] != value).collect::<Vec<_>>();
+                if nodes.is_empty() {
+                    return None
+                }
+                let node = nodes[rng.gen_range(0..nodes.len())];
+                mutated.items[item].1 = Some(self.proof[node].clone());
+                mutated.proof[node] = value;
+            },
+            Mutation::LeafCountOffByOne => return None,
+        }
+        Some(mutated)
+    }
+}
@@ -0,0 +1,268 @@
+#![cfg(test)]
+
+use crate::evm_runner::{project_root, EvmRunner};
+use alloy_primitives::{Address, U256};
+use alloy_rlp::{Decodable, Header};
+use alloy_sol_types::{sol, SolCall};
+use proptest::{collection::vec, prelude::*};
+
+sol! {
+    function isList(bytes encoded) external pure returns (bool);
+    function payloadLen(bytes encoded) external pure returns (uint256);
+    function toBytes(bytes encoded) external pure returns (bytes);
+    function toUint(bytes encoded) external pure returns (uint256);
+    function toUintStrict(bytes encoded) external pure returns (uint256);
+    function toList(bytes encoded) external pure returns (uint256[] offsets, uint256[] lengths);
+}
+
+/// The `RLPReaderTest` harness deployed to an in-memory EVM.
+struct Reader {
+    runner: EvmRunner,
+    contract: Address,
+}
+
+impl Reader {
+    fn new() -> Self {
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let contract = runner.deploy(&project, "RLPReaderTest");
+        Self { runner, contract }
+    }
+
+    fn call<C: SolCall>(&mut self, call: C) -> Result<C::Return, String> {
+        self.runner.call_sol(self.contract, call)
+    }
+
+    fn is_list(&mut self, encoded: &[u8]) -> Result<bool, String> {
+        Ok(self.call(isListCall { encoded: encoded.to_vec().into() })?._0)
+    }
+
+    fn payload_len(&mut self, encoded: &[u8]) -> Result<usize, String> {
+        Ok(self.call(payloadLenCall { encoded: encoded.to_vec().into() })?._0.to())
+    }
+
+    fn to_bytes(&mut self, encoded: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(self.call(toBytesCall { encoded: encoded.to_vec().into() })?._0.to_vec())
+    }
+
+    fn to_uint(&mut self, encoded: &[u8]) -> Result<U256, String> {
+        Ok(self.call(toUintCall { encoded: encoded.to_vec().into() })?._0)
+    }
+
+    fn to_uint_strict(&mut self, encoded: &[u8]) -> Result<U256, String> {
+        Ok(self.call(toUintStrictCall { encoded: encoded.to_vec().into() })?._0)
+    }
+
+    /// The offset and length of each list item within `encoded`.
+    fn to_list(&mut self, encoded: &[u8]) -> Result<Vec<(usize, usize)>, String> {
+        let items = self.call(toListCall { encoded: encoded.to_vec().into() })?;
+        Ok(items
+            .offsets
+            .iter()
+            .zip(&items.lengths)
+            .map(|(o, l)| (o.to(), l.to()))
+            .collect())
+    }
+}
+
+/// An RLP item, either a byte string or a list of items.
+#[derive(Debug, Clone)]
+enum Item {
+    String(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Item {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Item::String(bytes) => alloy_rlp::encode(&bytes[..]),
+            Item::List(items) => {
+                let payload = items.iter().flat_map(Item::encode).collect::<Vec<_>>();
+                list_with_payload(&payload)
+            },
+        }
+    }
+}
+
+fn list_with_payload(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    Header { list: true, payload_length: payload.len() }.encode(&mut out);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn arb_item() -> impl Strategy<Value = Item> {
+    vec(any::<u8>(), 0..80)
+        .prop_map(Item::String)
+        .prop_recursive(3, 64, 8, |inner| vec(inner, 0..8).prop_map(Item::List))
+}
+
+/// The offset and length of each item in the payload of an encoded list.
+fn item_extents(header_len: usize, items: &[Item]) -> Vec<(usize, usize)> {
+    let mut offset = header_len;
+    items
+        .iter()
+        .map(|item| {
+            let len = item.encode().len();
+            offset += len;
+            (offset - len, len)
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn test_strings_match_alloy_rlp(payload in vec(any::<u8>(), 0..200)) {
+        let encoded = alloy_rlp::encode(&payload[..]);
+        let mut reader = Reader::new();
+
+        prop_assert_eq!(Header::decode_bytes(&mut &encoded[..], false).unwrap(), &payload[..]);
+        prop_assert!(!reader.is_list(&encoded).unwrap());
+        prop_assert_eq!(reader.payload_len(&encoded).unwrap(), payload.len());
+        prop_assert_eq!(reader.to_bytes(&encoded).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_uints_match_alloy_rlp(bytes in any::<[u8; 32]>(), shift in 0usize..=256) {
+        let value = U256::from_be_bytes(bytes) >> shift;
+        let encoded = alloy_rlp::encode(value);
+        let mut reader = Reader::new();
+
+        prop_assert_eq!(U256::decode(&mut &encoded[..]).unwrap(), value);
+        prop_assert_eq!(reader.to_uint(&encoded).unwrap(), value);
+        // toUintStrict only accepts the 33 byte encoding of values with a non-zero top byte
+        match reader.to_uint_strict(&encoded) {
+            Ok(strict) => {
+                prop_assert_eq!(encoded.len(), 33);
+                prop_assert_eq!(strict, value);
+            },
+            Err(_) => prop_assert_ne!(encoded.len(), 33),
+        }
+    }
+
+    #[test]
+    fn test_lists_match_alloy_rlp(items in vec(arb_item(), 0..16)) {
+        let encoded = Item::List(items.clone()).encode();
+        let buf = &mut &encoded[..];
+        let header = Header::decode(buf).unwrap();
+        let header_len = encoded.len() - buf.len();
+        let mut reader = Reader::new();
+
+        prop_assert!(header.list);
+        prop_assert!(reader.is_list(&encoded).unwrap());
+        prop_assert_eq!(reader.payload_len(&encoded).unwrap(), header.payload_length);
+        prop_assert_eq!(reader.to_bytes(&encoded).unwrap(), buf.to_vec());
+        prop_assert_eq!(reader.to_list(&encoded).unwrap(), item_extents(header_len, &items));
+    }
+}
+
+// RLPReader does not check that lengths are canonical, so encodings that alloy-rlp rejects decode
+// to the payload they describe.
+proptest! {
+    #[test]
+    fn test_non_canonical_lengths(payload in vec(any::<u8>(), 1..56)) {
+        let mut reader = Reader::new();
+        let len = payload.len() as u8;
+
+        let mut cases = vec![
+            // short payloads with a long form length
+            ([&[0xb8, len][..], &payload[..]].concat(), alloy_rlp::Error::NonCanonicalSize),
+            // long form lengths with a leading zero
+            ([&[0xb9, 0x00, len][..], &payload[..]].concat(), alloy_rlp::Error::LeadingZero),
+        ];
+        if let [byte @ 0..=0x7f] = payload[..] {
+            // single bytes below 0x80 wrapped in a string
+            cases.push((vec![0x81, byte], alloy_rlp::Error::NonCanonicalSingleByte));
+        }
+
+        for (encoded, error) in cases {
+            prop_assert_eq!(Header::decode(&mut &encoded[..]), Err(error));
+            prop_assert!(!reader.is_list(&encoded).unwrap());
+            prop_assert_eq!(reader.payload_len(&encoded).unwrap(), payload.len());
+            prop_assert_eq!(reader.to_bytes(&encoded).unwrap(), payload.clone());
+        }
+    }
+
+    #[test]
+    fn test_non_canonical_list_lengths(
+        items in vec(vec(any::<u8>(), 0..12).prop_map(Item::String), 1..4),
+    ) {
+        // at most 3 items of 13 bytes, short enough for the single byte length form
+        let payload = items.iter().flat_map(Item::encode).collect::<Vec<_>>();
+        let encoded = [&[0xf8, payload.len() as u8][..], &payload[..]].concat();
+        let mut reader = Reader::new();
+
+        prop_assert_eq!(Header::decode(&mut &encoded[..]), Err(alloy_rlp::Error::NonCanonicalSize));
+        prop_assert!(reader.is_list(&encoded).unwrap());
+        prop_assert_eq!(reader.to_list(&encoded).unwrap(), item_extents(2, &items));
+    }
+}
+
+// RLPReader rejects items whose length prefix disagrees with the input it is given, whether the
+// input is truncated or the prefix claims more than it holds, and list items that overrun the list.
+proptest! {
+    #[test]
+    fn test_truncated_lists(items in vec(arb_item(), 1..16), cut in any::<prop::sample::Index>()) {
+        let encoded = Item::List(items.clone()).encode();
+        let buf = &mut &encoded[..];
+        Header::decode(buf).unwrap();
+        let header_len = encoded.len() - buf.len();
+        let truncated = &encoded[..header_len + cut.index(buf.len())];
+        let mut reader = Reader::new();
+
+        prop_assert!(Header::decode(&mut &truncated[..]).is_err());
+        prop_assert!(reader.is_list(truncated).is_err());
+        prop_assert!(reader.payload_len(truncated).is_err());
+        prop_assert!(reader.to_list(truncated).is_err());
+
+        // refitted to the payload left, the list ends with an item cut short unless the cut falls
+        // between two items
+        let relisted = list_with_payload(&truncated[header_len..]);
+        let relisted_header_len = relisted.len() - (truncated.len() - header_len);
+        prop_assert!(reader.is_list(&relisted).unwrap());
+        let starts = item_extents(header_len, &items).into_iter().map(|(offset, _)| offset);
+        match starts.position(|offset| offset == truncated.len()) {
+            Some(complete) => prop_assert_eq!(
+                reader.to_list(&relisted).unwrap(),
+                item_extents(relisted_header_len, &items[..complete])
+            ),
+            None => prop_assert!(reader.to_list(&relisted).is_err()),
+        }
+    }
+
+    #[test]
+    fn test_oversized_length_prefixes(
+        len_of_len in 1usize..=8,
+        claimed in any::<u64>(),
+        payload in vec(any::<u8>(), 0..64),
+        list in any::<bool>(),
+    ) {
+        // a length of `len_of_len` bytes with a non-zero top byte, longer than the payload
+        let claimed = (claimed >> (64 - 8 * len_of_len)) | (0x80u64 << (8 * (len_of_len - 1)));
+        prop_assume!(claimed as usize > payload.len());
+        let prefix = (if list { 0xf7 } else { 0xb7 }) + len_of_len as u8;
+        let header = [&[prefix][..], &claimed.to_be_bytes()[8 - len_of_len..]].concat();
+        let encoded = [&header[..], &payload[..]].concat();
+        let mut reader = Reader::new();
+
+        prop_assert!(Header::decode(&mut &encoded[..]).is_err());
+        prop_assert!(reader.is_list(&encoded).is_err());
+        prop_assert!(reader.payload_len(&encoded).is_err());
+        prop_assert!(reader.to_bytes(&encoded).is_err());
+
+        // nested in a list, the item overruns it
+        let outer = list_with_payload(&encoded);
+        prop_assert!(reader.is_list(&outer).unwrap());
+        prop_assert!(reader.to_list(&outer).is_err());
+    }
+
+    #[test]
+    fn test_trailing_bytes(item in arb_item(), trailing in vec(any::<u8>(), 1..8)) {
+        let encoded = [item.encode(), trailing].concat();
+        let mut reader = Reader::new();
+
+        prop_assert!(reader.is_list(&encoded).is_err());
+        prop_assert!(reader.payload_len(&encoded).is_err());
+        prop_assert!(reader.to_bytes(&encoded).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+#![cfg(test)]
+
+use crate::evm_runner::{project_root, EvmRunner};
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{sol, SolCall};
+use codec::{Compact, Decode, Encode};
+use proptest::{collection::vec, prelude::*};
+
+sol! {
+    function decodeUint256(bytes data) external pure returns (uint256);
+    function decodeUintCompact(bytes data) external pure returns (uint256 value, uint256 offset);
+    function decodeUintCompactWithMode(bytes data) external pure returns (uint256 value, uint8 mode);
+    function encodeUintCompact(uint256 v) external pure returns (bytes);
+    function encodeBytes(bytes input) external pure returns (bytes);
+    function reverse256(uint256 input) external pure returns (uint256);
+    function reverse128(uint128 input) external pure returns (uint128);
+    function reverse64(uint64 input) external pure returns (uint64);
+    function reverse32(uint32 input) external pure returns (uint32);
+    function reverse16(uint16 input) external pure returns (uint16);
+}
+
+/// The `ScaleCodecTest` harness deployed to an in-memory EVM.
+struct ScaleCodec {
+    runner: EvmRunner,
+    contract: Address,
+}
+
+impl ScaleCodec {
+    fn new() -> Self {
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let contract = runner.deploy(&project, "ScaleCodecTest");
+        Self { runner, contract }
+    }
+
+    fn call<C: SolCall>(&mut self, call: C) -> Result<C::Return, String> {
+        self.runner.call_sol(self.contract, call)
+    }
+
+    /// The decoded value and the number of bytes read.
+    fn decode_compact(&mut self, data: &[u8]) -> Result<(U256, usize), String> {
+        let decoded = self.call(decodeUintCompactCall { data: data.to_vec().into() })?;
+        Ok((decoded.value, decoded.offset.to()))
+    }
+
+    /// The decoded value and the compact mode, using the `bytes` overload.
+    fn decode_compact_with_mode(&mut self, data: &[u8]) -> Result<(U256, u8), String> {
+        let decoded = self.call(decodeUintCompactWithModeCall { data: data.to_vec().into() })?;
+        Ok((decoded.value, decoded.mode))
+    }
+}
+
+/// Values around each power of two, covering the boundaries of every compact mode and of every
+/// big-integer length, alongside uniformly random values.
+fn arb_compact_value() -> impl Strategy<Value = u128> {
+    prop_oneof![
+        (0u32..128, -2i128..=2)
+            .prop_map(|(bits, delta)| (1u128 << bits).saturating_add_signed(delta)),
+        Just(u128::MAX),
+        any::<u128>(),
+    ]
+}
+
+#[test]
+fn test_compact_mode_boundaries() {
+    let mut codec = ScaleCodec::new();
+    let boundaries: [(u128, usize); 8] = [
+        (0, 1),
+        (63, 1),
+        (64, 2),
+        ((1 << 14) - 1, 2),
+        (1 << 14, 4),
+        ((1 << 30) - 1, 4),
+        (1 << 30, 5),
+        (u64::MAX as u128, 9),
+    ];
+
+    for (value, len) in boundaries {
+        let encoded = Compact(value).encode();
+        assert_eq!(encoded.len(), len, "value={value}");
+
+        let call = encodeUintCompactCall { v: U256::from(value) };
+        assert_eq!(codec.call(call).unwrap()._0.to_vec(), encoded, "value={value}");
+        assert_eq!(codec.decode_compact(&encoded).unwrap(), (U256::from(value), len));
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_encode_uint_compact(value in arb_compact_value()) {
+        let mut codec = ScaleCodec::new();
+        let encoded = codec.call(encodeUintCompactCall { v: U256::from(value) }).unwrap()._0;
+
+        prop_assert_eq!(encoded.to_vec(), Compact(value).encode());
+    }
+
+    /// The `ByteSlice` decoder reads big integers of at most 8 bytes, the `bytes` overload none.
+    #[test]
+    fn test_decode_uint_compact(value in arb_compact_value(), junk in vec(any::<u8>(), 0..4)) {
+        let mut codec = ScaleCodec::new();
+        let encoded = Compact(value).encode();
+        let input = [&encoded[..], &junk[..]].concat();
+
+        match u64::try_from(value) {
+            Ok(_) => prop_assert_eq!(
+                codec.decode_compact(&input).unwrap(),
+                (U256::from(value), encoded.len())
+            ),
+            Err(_) => prop_assert!(codec.decode_compact(&input).is_err()),
+        }
+
+        let mode = encoded[0] & 0b11;
+        match codec.decode_compact_with_mode(&input) {
+            Ok(decoded) => {
+                prop_assert!(mode < 3);
+                prop_assert_eq!(decoded, (U256::from(value), mode));
+            },
+            Err(_) => prop_assert_eq!(mode, 3),
+        }
+    }
+
+    /// Both decoders reject values encoded in a wider mode than they need.
+    #[test]
+    fn test_decode_non_canonical_compact(value in 0u64..(1 << 30)) {
+        let mut codec = ScaleCodec::new();
+        let mut cases = vec![
+            // four-byte mode
+            (((value as u32) << 2) | 0b10).to_le_bytes().to_vec(),
+            // big-integer mode of 4 bytes
+            [&[0b11][..], &(value as u32).to_le_bytes()].concat(),
+            // big-integer mode of 5 bytes with a trailing zero
+            [&[0b111][..], &value.to_le_bytes()[..5]].concat(),
+        ];
+        if value < 1 << 14 {
+            // two-byte mode
+            cases.push((((value as u16) << 2) | 0b01).to_le_bytes().to_vec());
+        }
+
+        for encoded in cases {
+            if Compact(value).encode() == encoded {
+                continue
+            }
+            prop_assert!(<Compact<u64>>::decode(&mut &encoded[..]).is_err());
+            prop_assert!(codec.decode_compact(&encoded).is_err());
+            prop_assert!(codec.decode_compact_with_mode(&encoded).is_err());
+        }
+    }
+
+    /// Big-integer mode values with a zero top byte need fewer bytes than they are given.
+    #[test]
+    fn test_decode_big_integer_compact_with_leading_zero(value in (1u64 << 30)..(1 << 56)) {
+        let mut codec = ScaleCodec::new();
+        // one more byte than the value needs
+        let len = 8 - value.leading_zeros() as usize / 8 + 1;
+        let prefix = ((len as u8 - 4) << 2) | 0b11;
+        let encoded = [&[prefix][..], &value.to_le_bytes()[..len]].concat();
+
+        prop_assert!(<Compact<u64>>::decode(&mut &encoded[..]).is_err());
+        prop_assert!(codec.decode_compact(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_truncated_compact(
+        value in arb_compact_value(),
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let mut codec = ScaleCodec::new();
+        let encoded = Compact(value).encode();
+        let truncated = &encoded[..cut.index(encoded.len())];
+
+        prop_assert!(<Compact<u128>>::decode(&mut &truncated[..]).is_err());
+        prop_assert!(codec.decode_compact(truncated).is_err());
+        prop_assert!(codec.decode_compact_with_mode(truncated).is_err());
+    }
+
+    #[test]
+    fn test_decode_uint256(data in vec(any::<u8>(), 0..40)) {
+        let mut codec = ScaleCodec::new();
+        let decoded = codec.call(decodeUint256Call { data: data.clone().into() });
+
+        if data.len() > 32 {
+            prop_assert!(decoded.is_err());
+            return Ok(())
+        }
+        let decoded = decoded.unwrap()._0;
+        prop_assert_eq!(decoded, U256::from_le_slice(&data));
+
+        // parity-scale-codec agrees wherever it has an integer of the same width
+        let input = &mut &data[..];
+        let scale = match data.len() {
+            1 => u8::decode(input).map(U256::from),
+            2 => u16::decode(input).map(U256::from),
+            4 => u32::decode(input).map(U256::from),
+            8 => u64::decode(input).map(U256::from),
+            16 => u128::decode(input).map(U256::from),
+            _ => return Ok(()),
+        };
+        prop_assert_eq!(decoded, scale.unwrap());
+    }
+
+    #[test]
+    fn test_reverse(
+        v256 in any::<[u8; 32]>(),
+        v128 in any::<u128>(),
+        v64 in any::<u64>(),
+        v32 in any::<u32>(),
+        v16 in any::<u16>(),
+    ) {
+        let mut codec = ScaleCodec::new();
+        let v256 = U256::from_be_bytes(v256);
+
+        // reversing the bytes of a big-endian integer gives its SCALE encoding
+        let reversed = codec.call(reverse256Call { input: v256 }).unwrap()._0;
+        prop_assert_eq!(reversed.to_be_bytes::<32>(), v256.to_le_bytes::<32>());
+        let reversed = codec.call(reverse128Call { input: v128 }).unwrap()._0;
+        prop_assert_eq!(reversed.to_be_bytes().to_vec(), v128.encode());
+        let reversed = codec.call(reverse64Call { input: v64 }).unwrap()._0;
+        prop_assert_eq!(reversed.to_be_bytes().to_vec(), v64.encode());
+        let reversed = codec.call(reverse32Call { input: v32 }).unwrap()._0;
+        prop_assert_eq!(reversed.to_be_bytes().to_vec(), v32.encode());
+        let reversed = codec.call(reverse16Call { input: v16 }).unwrap()._0;
+        prop_assert_eq!(reversed.to_be_bytes().to_vec(), v16.encode());
+    }
+
+    /// Lengths either side of 2^14 switch the length prefix from two-byte to four-byte mode.
+    #[test]
+    fn test_encode_bytes(
+        input in prop_oneof![vec(any::<u8>(), 0..100), vec(any::<u8>(), 16380..16390)],
+    ) {
+        let mut codec = ScaleCodec::new();
+        let encoded = codec.call(encodeBytesCall { input: input.clone().into() }).unwrap()._0;
+
+        prop_assert_eq!(encoded.to_vec(), input.encode());
+        prop_assert_eq!(<Vec<u8>>::decode(&mut &encoded[..]).unwrap(), input);
+    }
+}
@@ -0,0 +1,274 @@
+//! Storage proofs produced the way a node produces them, by `sp_state_machine` reading from a state
+//! backend, rather than by recording lookups in a trie built by hand, so that the proofs handed to
+//! `VerifySubstrateProof` and `ReadChildProofCheck` have the shape of those returned by RPCs.
+
+use crate::trie_utils::TrieProof;
+use codec::{Codec, DecodeAll};
+use sp_core::{
+    storage::{ChildInfo, StateVersion, Storage, StorageChild},
+    Hasher,
+};
+use sp_state_machine::{Backend, InMemoryBackend, TestExternalities};
+use sp_trie::{CompactProof, StorageProof};
+use std::collections::BTreeMap;
+
+/// The state of a chain: the entries of its main trie and of its default child tries.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    pub top: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub children: Vec<(ChildInfo, BTreeMap<Vec<u8>, Vec<u8>>)>,
+}
+
+impl State {
+    /// Externalities holding the state, whose tries are hashed with `H` and follow the layout of
+    /// `state_version`.
+    pub fn externalities<H>(&self, state_version: StateVersion) -> TestExternalities<H>
+    where
+        H: Hasher + 'static,
+        H::Out: Ord + Codec + 'static,
+    {
+        let children_default = self
+            .children
+            .iter()
+            .map(|(child_info, data)| {
+                let child = StorageChild { data: data.clone(), child_info: child_info.clone() };
+                (child_info.storage_key().to_vec(), child)
+            })
+            .collect();
+        let storage = Storage { top: self.top.clone(), children_default };
+        TestExternalities::new_with_state_version(storage, state_version)
+    }
+
+    /// The backend of the state, whose root is the state root.
+    pub fn backend<H>(&self, state_version: StateVersion) -> InMemoryBackend<H>
+    where
+        H: Hasher + 'static,
+        H::Out: Ord + Codec + 'static,
+    {
+        self.externalities(state_version).as_backend()
+    }
+}
+
+fn proof_nodes(proof: StorageProof) -> Vec<Vec<u8>> {
+    proof.into_iter_nodes().collect()
+}
+
+/// Prove `keys` of the main trie with `sp_state_machine::prove_read`, as `state_getReadProof` does.
+pub fn prove_read<H>(backend: &InMemoryBackend<H>, keys: &[Vec<u8>]) -> TrieProof<H::Out>
+where
+    H: Hasher,
+    H::Out: Ord + Codec,
+{
+    let proof = sp_state_machine::prove_read(backend.clone(), keys).unwrap();
+    let items = keys.iter().map(|key| (key.clone(), backend.storage(key).unwrap())).collect();
+    TrieProof { root: *backend.root(), proof: proof_nodes(proof), items }
+}
+
+/// Prove `keys` of the child trie `child_info` with `sp_state_machine::prove_child_read`, as
+/// `state_getChildReadProof` does. The proof holds both the path to the child trie's root in the
+/// main trie, whose root is the one returned, and the nodes of the child trie.
+pub fn prove_child_read<H>(
+    backend: &InMemoryBackend<H>,
+    child_info: &ChildInfo,
+    keys: &[Vec<u8>],
+) -> TrieProof<H::Out>
+where
+    H: Hasher,
+    H::Out: Ord + Codec,
+{
+    let proof = sp_state_machine::prove_child_read(backend.clone(), child_info, keys).unwrap();
+    let items = keys
+        .iter()
+        .map(|key| (key.clone(), backend.child_storage(child_info, key).unwrap()))
+        .collect();
+    TrieProof { root: *backend.root(), proof: proof_nodes(proof), items }
+}
+
+/// Expand a SCALE encoded `CompactProof`, as shipped by nodes with the hashes of the children it
+/// holds left out, into the proof nodes `VerifySubstrateProof` and `ReadChildProofCheck` take.
+/// The proof must be of the trie whose root is `root`, with no nodes missing or left over. The
+/// child tries whose roots it holds may be left out, as they are from proofs of the main trie
+/// alone, so a proof lacking the child trie it was meant to read expands without error and only
+/// fails once verified.
+pub fn expand_compact_proof<H: Hasher>(
+    encoded: &[u8],
+    root: H::Out,
+) -> Result<Vec<Vec<u8>>, String> {
+    let compact = CompactProof::decode_all(&mut &encoded[..])
+        .map_err(|e| format!("invalid compact proof: {e}"))?;
+    if compact.encoded_nodes.is_empty() {
+        return Err("compact proof has no nodes".to_string())
+    }
+    let (proof, _) = compact
+        .to_storage_proof::<H>(Some(&root))
+        .map_err(|e| format!("inconsistent compact proof: {e}"))?;
+    Ok(proof.into_iter_nodes().collect())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        merkle_patricia::{assert_values, setup, ReadChildCall, VerifyKeysCall},
+        seeded_rng,
+    };
+    use alloy_primitives::FixedBytes;
+    use alloy_sol_types::SolCall;
+    use codec::Encode;
+    use primitive_types::H256;
+    use rand::{rngs::StdRng, Rng};
+    use sp_core::KeccakHasher;
+
+    /// A state with values on either side of the 32 byte threshold above which `LayoutV1` hashes
+    /// them, and a child trie.
+    pub(crate) fn arb_state(rng: &mut StdRng, child_info: &ChildInfo) -> State {
+        let entry = |rng: &mut StdRng| {
+            let value = (0..rng.gen_range(1..64)).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+            (rng.gen::<[u8; 32]>().to_vec(), value)
+        };
+        let top = (0..rng.gen_range(1..200)).map(|_| entry(rng)).collect();
+        let child = (0..rng.gen_range(1..200)).map(|_| entry(rng)).collect();
+        State { top, children: vec![(child_info.clone(), child)] }
+    }
+
+    /// Present and absent keys of `entries`.
+    pub(crate) fn read_keys(
+        rng: &mut impl Rng,
+        entries: &BTreeMap<Vec<u8>, Vec<u8>>,
+    ) -> Vec<Vec<u8>> {
+        let mut keys = entries.keys().filter(|_| rng.gen_bool(0.2)).cloned().collect::<Vec<_>>();
+        keys.extend((0..3).map(|_| rng.gen::<[u8; 32]>().to_vec()));
+        keys
+    }
+
+    /// The `CompactProof` nodes ship the proof in.
+    fn compact_proof(proof: &TrieProof<H256>) -> CompactProof {
+        StorageProof::new(proof.proof.clone())
+            .into_compact_proof::<KeccakHasher>(proof.root)
+            .unwrap()
+    }
+
+    /// Round trip the proof through its `CompactProof` encoding.
+    fn compact_round_trip(proof: &TrieProof<H256>) -> Vec<Vec<u8>> {
+        let encoded = compact_proof(proof).encode();
+        let nodes = expand_compact_proof::<KeccakHasher>(&encoded, proof.root).unwrap();
+        let mut expected = proof.proof.clone();
+        expected.sort();
+        let mut sorted = nodes.clone();
+        sorted.sort();
+        assert_eq!(sorted, expected);
+        nodes
+    }
+
+    #[test]
+    fn test_state_machine_read_proof() {
+        let mut rng = seeded_rng();
+        let child_info = ChildInfo::new_default(b"child");
+        let (mut runner, addr) = setup();
+
+        for state_version in [StateVersion::V0, StateVersion::V1] {
+            let state = arb_state(&mut rng, &child_info);
+            let backend = state.backend::<KeccakHasher>(state_version);
+            let keys = read_keys(&mut rng, &state.top);
+            let proof = prove_read(&backend, &keys);
+
+            for nodes in [proof.proof.clone(), compact_round_trip(&proof)] {
+                let call = VerifyKeysCall {
+                    root: FixedBytes(proof.root.0),
+                    proof: nodes.into_iter().map(Into::into).collect(),
+                    keys: keys.iter().cloned().map(Into::into).collect(),
+                };
+                let result = runner.call_raw(addr, call.abi_encode());
+                let values = VerifyKeysCall::abi_decode_returns(&result, true).unwrap()._0;
+                assert_values(&values, &proof.items);
+            }
+            for (key, value) in &proof.items {
+                assert_eq!(value.as_ref(), state.top.get(key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_machine_child_read_proof() {
+        let mut rng = seeded_rng();
+        let child_info = ChildInfo::new_default(b"child");
+        let (mut runner, addr) = setup();
+
+        for state_version in [StateVersion::V0, StateVersion::V1] {
+            let state = arb_state(&mut rng, &child_info);
+            let backend = state.backend::<KeccakHasher>(state_version);
+            let keys = read_keys(&mut rng, &state.children[0].1);
+            let proof = prove_child_read(&backend, &child_info, &keys);
+
+            for nodes in [proof.proof.clone(), compact_round_trip(&proof)] {
+                let call = ReadChildCall {
+                    root: FixedBytes(proof.root.0),
+                    proof: nodes.into_iter().map(Into::into).collect(),
+                    keys: keys.iter().cloned().map(Into::into).collect(),
+                    childInfo: child_info.storage_key().to_vec().into(),
+                };
+                let result = runner.call_raw(addr, call.abi_encode());
+                let values = ReadChildCall::abi_decode_returns(&result, true).unwrap()._0;
+                assert_values(&values, &proof.items);
+            }
+            for (key, value) in &proof.items {
+                assert_eq!(value.as_ref(), state.children[0].1.get(key));
+            }
+
+            // the child trie's root can't be read from a proof of the main trie alone
+            let top_keys = read_keys(&mut rng, &state.top);
+            let top_proof = prove_read(&backend, &top_keys);
+            let call = ReadChildCall {
+                root: FixedBytes(top_proof.root.0),
+                proof: top_proof.proof.into_iter().map(Into::into).collect(),
+                keys: keys.iter().cloned().map(Into::into).collect(),
+                childInfo: child_info.storage_key().to_vec().into(),
+            };
+            assert!(runner.call_may_revert(addr, call.abi_encode()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_expand_compact_proof_errors() {
+        let mut rng = seeded_rng();
+        let child_info = ChildInfo::new_default(b"child");
+        let state = arb_state(&mut rng, &child_info);
+        let backend = state.backend::<KeccakHasher>(StateVersion::V1);
+        let keys = read_keys(&mut rng, &state.children[0].1);
+        let proof = prove_child_read(&backend, &child_info, &keys);
+        let compact = compact_proof(&proof);
+        let expand = |compact: &CompactProof, root| {
+            expand_compact_proof::<KeccakHasher>(&compact.encode(), root)
+        };
+        assert!(expand(&compact, proof.root).is_ok());
+
+        assert!(expand(&compact, H256(rng.gen())).is_err());
+        assert!(expand(&CompactProof { encoded_nodes: vec![] }, proof.root).is_err());
+        assert!(expand_compact_proof::<KeccakHasher>(&[0xff; 8], proof.root).is_err());
+        let trailing = [compact.encode(), vec![0]].concat();
+        assert!(expand_compact_proof::<KeccakHasher>(&trailing, proof.root).is_err());
+
+        // nodes missing or left over
+        let mut missing = compact.clone();
+        missing.encoded_nodes.remove(0);
+        assert!(expand(&missing, proof.root).is_err());
+        let mut extra = compact.clone();
+        extra.encoded_nodes.push(compact.encoded_nodes[0].clone());
+        assert!(expand(&extra, proof.root).is_err());
+
+        // a proof of the main trie alone lacks the child trie its root commits to, which expanding
+        // it can't tell from a read of the child root itself, so only the verifier rejects
+        // it
+        let top =
+            compact_proof(&prove_read(&backend, &[child_info.prefixed_storage_key().into_inner()]));
+        let expanded = expand(&top, proof.root).unwrap();
+        let call = ReadChildCall {
+            root: FixedBytes(proof.root.0),
+            proof: expanded.into_iter().map(Into::into).collect(),
+            keys: keys.iter().cloned().map(Into::into).collect(),
+            childInfo: child_info.storage_key().to_vec().into(),
+        };
+        let (mut runner, addr) = setup();
+        assert!(runner.call_may_revert(addr, call.abi_encode()).is_err());
+    }
+}
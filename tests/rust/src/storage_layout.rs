@@ -0,0 +1,378 @@
+//! Storage slots of Solidity state variables, following the layout described in the Solidity docs,
+//! and the trie keys and values under which `VerifyEthereumProof` proves them.
+
+use alloy_primitives::{keccak256, B256, U256};
+use alloy_rlp::Decodable;
+
+/// Where a value lives in storage: `size` bytes of the word at `slot`, starting `offset` bytes from
+/// its low order end. Value types smaller than a word are packed together this way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageLocation {
+    pub slot: B256,
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl StorageLocation {
+    /// A value occupying the whole word at `slot`.
+    pub fn word(slot: B256) -> Self {
+        Self { slot, offset: 0, size: 32 }
+    }
+
+    /// The key of the slot in the account's storage trie.
+    pub fn trie_key(&self) -> B256 {
+        trie_key(self.slot)
+    }
+
+    /// Extract the value from the word stored at the slot.
+    pub fn extract(&self, word: U256) -> U256 {
+        let value = word >> (self.offset * 8);
+        match self.size {
+            32 => value,
+            size => value & ((U256::from(1) << (size * 8)) - U256::from(1)),
+        }
+    }
+}
+
+/// The locations of the members of a struct stored at `slot`, whose members are value types of
+/// `sizes` bytes in declaration order. Each member is packed into the slot of the member before it
+/// when it fits in the bytes left there, and starts the next slot otherwise. State variables
+/// declared one after another are packed the same way from the slot of the first.
+pub fn struct_members(slot: B256, sizes: &[usize]) -> Vec<StorageLocation> {
+    let (mut index, mut offset) = (0u64, 0);
+    sizes
+        .iter()
+        .map(|&size| {
+            assert!((1..=32).contains(&size), "members of {size} bytes are not value types");
+            if offset + size > 32 {
+                index += 1;
+                offset = 0;
+            }
+            let location =
+                StorageLocation { slot: slot_offset(slot, U256::from(index)), offset, size };
+            offset += size;
+            location
+        })
+        .collect()
+}
+
+/// The number of slots taken by a struct whose members are value types of `sizes` bytes, packed as
+/// by [`struct_members`].
+pub fn struct_slots(sizes: &[usize]) -> u64 {
+    struct_members(B256::ZERO, sizes)
+        .last()
+        .map_or(0, |last| U256::from_be_bytes(last.slot.0).to::<u64>() + 1)
+}
+
+/// The slot of the state variable declared at `index`, for variables that each take a whole slot.
+pub fn slot(index: u64) -> B256 {
+    B256::from(U256::from(index).to_be_bytes::<32>())
+}
+
+/// The slot `n` slots after `slot`, such as the `n`th slot of a struct stored at `slot`.
+pub fn slot_offset(slot: B256, n: U256) -> B256 {
+    B256::from(U256::from_be_bytes(slot.0).wrapping_add(n).to_be_bytes::<32>())
+}
+
+/// The slot of the value of `key` in the mapping at `slot`, for keys of value types, which are
+/// padded to a word as by `abi.encode`.
+pub fn mapping_slot(slot: B256, key: B256) -> B256 {
+    keccak256([key.as_slice(), slot.as_slice()].concat())
+}
+
+/// The slot of the value of `key` in the mapping at `slot`, for `bytes` and `string` keys, which
+/// are hashed as they are.
+pub fn mapping_slot_bytes(slot: B256, key: &[u8]) -> B256 {
+    keccak256([key, slot.as_slice()].concat())
+}
+
+/// The slot of the value of `keys` in the nested mapping at `slot`, outermost key first.
+pub fn nested_mapping_slot(slot: B256, keys: &[B256]) -> B256 {
+    keys.iter().fold(slot, |slot, key| mapping_slot(slot, *key))
+}
+
+/// The location of element `index` of the dynamic array at `slot`, whose elements are value types
+/// of `size` bytes. Elements of up to 16 bytes are packed several to a slot.
+pub fn array_element(slot: B256, index: u64, size: usize) -> StorageLocation {
+    assert!((1..=32).contains(&size), "elements of {size} bytes are not value types");
+    let per_slot = (32 / size) as u64;
+    let data = slot_offset(keccak256(slot), U256::from(index / per_slot));
+    StorageLocation { slot: data, offset: (index % per_slot) as usize * size, size }
+}
+
+/// The first slot of element `index` of the dynamic array at `slot`, whose elements, such as
+/// structs or static arrays, take `slots` whole slots each.
+pub fn array_element_slot(slot: B256, index: u64, slots: u64) -> B256 {
+    slot_offset(keccak256(slot), U256::from(index) * U256::from(slots))
+}
+
+/// The slots holding the data of a `bytes` or `string` of `len` bytes stored at `slot`, none for
+/// values shorter than a word, which are stored in the word at `slot` itself.
+pub fn bytes_data_slots(slot: B256, len: usize) -> Vec<B256> {
+    data_slots(slot, len).collect()
+}
+
+fn data_slots(slot: B256, len: usize) -> impl Iterator<Item = B256> {
+    let data = keccak256(slot);
+    let count = if len < 32 { 0 } else { len.div_ceil(32) };
+    (0..count).map(move |i| slot_offset(data, U256::from(i)))
+}
+
+/// The longest `bytes` or `string` read by [`decode_bytes`], far longer than any value written in a
+/// single block, so that a length read from an unchecked word can't make it read without end.
+pub const MAX_BYTES_LEN: usize = 1 << 20;
+
+/// Decode the `bytes` or `string` stored at `slot` from `word`, the word at `slot`, reading the
+/// data slots of values of a word or longer with `read`. Fails on words that Solidity could not
+/// have stored for the value, and on values longer than [`MAX_BYTES_LEN`].
+pub fn decode_bytes(
+    slot: B256,
+    word: U256,
+    mut read: impl FnMut(B256) -> U256,
+) -> Result<Vec<u8>, String> {
+    let bytes = word.to_be_bytes::<32>();
+    // short values keep twice their length in the lowest byte, long values twice their length
+    // plus one in the whole word
+    if bytes[31] & 1 == 0 {
+        let len = bytes[31] as usize / 2;
+        if len > 31 {
+            return Err(format!("short value of {len} bytes at {slot}"))
+        }
+        // the bytes past the value are cleared when it is written
+        if bytes[len..31].iter().any(|byte| *byte != 0) {
+            return Err(format!("short value at {slot} followed by non-zero bytes"))
+        }
+        return Ok(bytes[..len].to_vec())
+    }
+    let len = word >> 1;
+    if len > U256::from(MAX_BYTES_LEN) {
+        return Err(format!("value of {len} bytes at {slot} is too long to read"))
+    }
+    let len = len.to::<usize>();
+    if len < 32 {
+        return Err(format!("long value of {len} bytes at {slot}"))
+    }
+    let mut data = Vec::new();
+    for slot in data_slots(slot, len) {
+        data.extend(read(slot).to_be_bytes::<32>());
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+/// The key of `slot` in the account's storage trie.
+pub fn trie_key(slot: B256) -> B256 {
+    keccak256(slot)
+}
+
+/// Decode a value proven by `VerifyEthereumProof` in a storage trie, where slots hold the RLP
+/// encoding of their word and zero words are absent.
+pub fn decode_storage_value(rlp: &[u8]) -> Result<U256, alloy_rlp::Error> {
+    if rlp.is_empty() {
+        return Ok(U256::ZERO)
+    }
+    let buf = &mut &rlp[..];
+    let value = U256::decode(buf)?;
+    if !buf.is_empty() {
+        return Err(alloy_rlp::Error::UnexpectedLength)
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eip1186::VerifyEthereumProofCall,
+        ethereum_trie::EthereumLayout,
+        evm_runner::{project_root, EvmRunner},
+        seeded_rng,
+        trie_utils::{generate_trie_proof, TrieProof},
+    };
+    use alloy_primitives::Address;
+    use alloy_sol_types::{sol, SolCall};
+    use rand::Rng;
+
+    sol! {
+        struct Member {
+            uint128 balance;
+            uint64 nonce;
+            address owner;
+            uint256 total;
+        }
+
+        function setValue(uint256 value) external;
+        function setPacked(uint128 low, uint64 mid, bool flag) external;
+        function setBalance(address owner, uint256 balance) external;
+        function setAllowance(address owner, uint256 id, bytes32 allowance) external;
+        function setName(string name, uint256 id) external;
+        function pushNumber(uint256 number) external;
+        function pushPackedNumber(uint64 number) external;
+        function setMember(Member member) external;
+        function pushMember(Member member) external;
+        function setData(bytes data) external;
+        function setText(string text) external;
+    }
+
+    /// The sizes of the members of `Member`, in declaration order.
+    const MEMBER_SIZES: [usize; 4] = [16, 8, 20, 32];
+
+    fn arb_member(rng: &mut impl Rng) -> Member {
+        Member {
+            balance: rng.gen(),
+            nonce: rng.gen(),
+            owner: Address::from(rng.gen::<[u8; 20]>()),
+            total: U256::from(rng.gen::<u128>()),
+        }
+    }
+
+    /// The slots computed for each kind of state variable hold the values `StorageLayoutFixture`
+    /// stored there, and their trie keys prove those values in a storage trie built from the
+    /// contract's storage.
+    #[test]
+    fn test_storage_layout_slots() {
+        let mut rng = seeded_rng();
+        let project = project_root();
+        let mut runner = EvmRunner::new();
+        let fixture = runner.deploy(&project, "StorageLayoutFixture");
+
+        let value = U256::from(rng.gen::<u128>());
+        let (low, mid) = (rng.gen::<u128>(), rng.gen::<u64>());
+        let owner = Address::from(rng.gen::<[u8; 20]>());
+        let balance = U256::from(rng.gen::<u64>());
+        let id = U256::from(rng.gen::<u64>());
+        let allowance = B256::from(rng.gen::<[u8; 32]>());
+        let numbers = (0..3).map(|_| U256::from(rng.gen::<u128>())).collect::<Vec<_>>();
+        let packed_numbers = (0..5).map(|_| rng.gen::<u64>()).collect::<Vec<_>>();
+        let member = arb_member(&mut rng);
+        let members = (0..2).map(|_| arb_member(&mut rng)).collect::<Vec<_>>();
+        // long enough to be stored in data slots, while the text fits in its own slot
+        let data = (0..70).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+        let text = "short string".to_string();
+
+        let mut calls = vec![
+            setValueCall { value }.abi_encode(),
+            setPackedCall { low, mid, flag: true }.abi_encode(),
+            setBalanceCall { owner, balance }.abi_encode(),
+            setAllowanceCall { owner, id, allowance }.abi_encode(),
+            setNameCall { name: text.clone(), id }.abi_encode(),
+            setMemberCall { member: member.clone() }.abi_encode(),
+            setDataCall { data: data.clone().into() }.abi_encode(),
+            setTextCall { text: text.clone() }.abi_encode(),
+        ];
+        calls.extend(numbers.iter().map(|number| pushNumberCall { number: *number }.abi_encode()));
+        calls.extend(
+            packed_numbers
+                .iter()
+                .map(|number| pushPackedNumberCall { number: *number }.abi_encode()),
+        );
+        calls.extend(
+            members
+                .iter()
+                .map(|member| pushMemberCall { member: member.clone() }.abi_encode()),
+        );
+        for call in calls {
+            runner.call_raw(fixture, call);
+        }
+
+        let storage = runner.storage(fixture);
+        let read =
+            |slot: B256| storage.get(&U256::from_be_bytes(slot.0)).copied().unwrap_or_default();
+        let locate = |location: StorageLocation| location.extract(read(location.slot));
+        let assert_member = |base: B256, member: &Member| {
+            let [balance, nonce, owner, total] =
+                struct_members(base, &MEMBER_SIZES).try_into().unwrap();
+            assert_eq!(locate(balance), U256::from(member.balance));
+            assert_eq!(locate(nonce), U256::from(member.nonce));
+            assert_eq!(locate(owner), U256::from_be_slice(member.owner.as_slice()));
+            assert_eq!(locate(total), member.total);
+        };
+
+        assert_eq!(read(slot(0)), value);
+        // `low`, `mid` and `flag` are packed into slot 1
+        let [low_at, mid_at, flag_at] = struct_members(slot(1), &[16, 8, 1]).try_into().unwrap();
+        assert_eq!(locate(low_at), U256::from(low));
+        assert_eq!(locate(mid_at), U256::from(mid));
+        assert_eq!(locate(flag_at), U256::from(1));
+        assert_eq!(read(mapping_slot(slot(2), owner.into_word())), balance);
+        let allowance_slot =
+            nested_mapping_slot(slot(3), &[owner.into_word(), B256::from(id.to_be_bytes::<32>())]);
+        assert_eq!(read(allowance_slot), U256::from_be_bytes(allowance.0));
+        assert_eq!(read(mapping_slot_bytes(slot(4), text.as_bytes())), id);
+
+        assert_eq!(read(slot(5)), U256::from(numbers.len()));
+        for (i, number) in numbers.iter().enumerate() {
+            assert_eq!(locate(array_element(slot(5), i as u64, 32)), *number);
+        }
+        assert_eq!(read(slot(6)), U256::from(packed_numbers.len()));
+        for (i, number) in packed_numbers.iter().enumerate() {
+            assert_eq!(locate(array_element(slot(6), i as u64, 8)), U256::from(*number));
+        }
+        assert_member(slot(7), &member);
+        assert_eq!(read(slot(10)), U256::from(members.len()));
+        for (i, member) in members.iter().enumerate() {
+            assert_member(
+                array_element_slot(slot(10), i as u64, struct_slots(&MEMBER_SIZES)),
+                member,
+            );
+        }
+        assert_eq!(decode_bytes(slot(11), read(slot(11)), read).unwrap(), data);
+        assert_eq!(decode_bytes(slot(12), read(slot(12)), read).unwrap(), text.as_bytes());
+
+        let entries = storage
+            .iter()
+            .map(|(slot, value)| {
+                (trie_key(B256::from(slot.to_be_bytes::<32>())).to_vec(), alloy_rlp::encode(value))
+            })
+            .collect::<Vec<_>>();
+        let slots = [
+            slot(1),
+            allowance_slot,
+            array_element(slot(6), 4, 8).slot,
+            array_element_slot(slot(10), 1, struct_slots(&MEMBER_SIZES)),
+            slot(13),
+        ];
+        let keys = slots.map(|slot| trie_key(slot).to_vec());
+        let TrieProof { root, proof, .. } = generate_trie_proof::<EthereumLayout>(&entries, &keys);
+
+        let library = runner.deploy(&project, "MerklePatricia");
+        let call = VerifyEthereumProofCall {
+            root: B256::from(root.0),
+            proof: proof.into_iter().map(Into::into).collect(),
+            keys: keys.iter().cloned().map(Into::into).collect(),
+        };
+        let result = runner.call_raw(library, call.abi_encode());
+        let values = VerifyEthereumProofCall::abi_decode_returns(&result, true).unwrap()._0;
+        for (slot, value) in slots.iter().zip(values) {
+            assert_eq!(decode_storage_value(&value.value).unwrap(), read(*slot), "slot={slot}");
+        }
+    }
+
+    /// Words Solidity could not have stored for a `bytes` or `string` are rejected instead of read.
+    #[test]
+    fn test_decode_bytes_rejects_malformed_words() {
+        let unread = |slot: B256| -> U256 { panic!("read data slot {slot}") };
+        // a short value claiming the whole word or more
+        for len in [32u8, 127] {
+            assert!(decode_bytes(slot(0), U256::from(len * 2), unread).is_err(), "len={len}");
+        }
+        // a long value claiming less than a word
+        assert!(decode_bytes(slot(0), U256::from(31 * 2 + 1), unread).is_err());
+        // a short value followed by non-zero bytes, after its last byte or in the padding
+        let short = U256::from(2 * 2) | (U256::from(0xaabbu64) << 240);
+        assert_eq!(decode_bytes(slot(0), short, unread).unwrap(), vec![0xaa, 0xbb]);
+        assert!(decode_bytes(slot(0), short | (U256::from(1) << 232), unread).is_err());
+        assert!(decode_bytes(slot(0), short | (U256::from(1) << 8), unread).is_err());
+        // a long value too long to be read
+        assert!(decode_bytes(slot(0), U256::MAX, unread).is_err());
+        assert!(
+            decode_bytes(slot(0), (U256::from(1u64 << 40) << 1) | U256::from(1), unread).is_err()
+        );
+        let too_long = (U256::from(MAX_BYTES_LEN + 1) << 1) | U256::from(1);
+        assert!(decode_bytes(slot(0), too_long, unread).is_err());
+
+        assert_eq!(decode_bytes(slot(0), U256::from(31 * 2), unread).unwrap(), vec![0; 31]);
+        let read = |_| U256::MAX;
+        assert_eq!(decode_bytes(slot(0), U256::from(33 * 2 + 1), read).unwrap(), vec![0xff; 33]);
+    }
+}
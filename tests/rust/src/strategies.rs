@@ -0,0 +1,63 @@
+//! Proptest strategies producing valid proofs for each of the Solidity verifiers, for property
+//! testing contracts that wrap them.
+
+use crate::{
+    mmr_utils::{generate_mmr_proof, MmrProof},
+    multi_proof_utils::{generate_multi_proof, MultiProof},
+    trie_utils::{generate_trie_proof, TrieProof},
+};
+use proptest::{
+    collection::{btree_map, vec},
+    prelude::*,
+    sample::subsequence,
+};
+use trie_db::{TrieHash, TrieLayout};
+
+/// A merkle tree of 2 to `max_leaves` leaves and a multi proof for a non-empty subset of them.
+/// Panics if `max_leaves` is less than 2.
+pub fn arb_multi_proof(max_leaves: usize) -> impl Strategy<Value = MultiProof> {
+    assert!(
+        max_leaves >= 2,
+        "multi proofs need trees of at least 2 leaves, max_leaves is {max_leaves}"
+    );
+    (2..=max_leaves)
+        .prop_flat_map(|num_leaves| {
+            (Just(num_leaves), subsequence((0..num_leaves).collect::<Vec<_>>(), 1..=num_leaves))
+        })
+        .prop_map(|(num_leaves, indices)| generate_multi_proof(num_leaves, &indices))
+}
+
+/// An MMR of 1 to `max_leaves` leaves and a proof for a non-empty subset of them. Panics if
+/// `max_leaves` is 0.
+pub fn arb_mmr_proof(max_leaves: u32) -> impl Strategy<Value = MmrProof> {
+    assert!(max_leaves >= 1, "MMR proofs need at least 1 leaf, max_leaves is 0");
+    (1..=max_leaves)
+        .prop_flat_map(|leaf_count| {
+            (
+                Just(leaf_count),
+                subsequence((0..leaf_count).collect::<Vec<_>>(), 1..=leaf_count as usize),
+            )
+        })
+        .prop_map(|(leaf_count, indices)| generate_mmr_proof(leaf_count, &indices))
+}
+
+/// A substrate trie of up to 64 entries and a proof for some of its keys along with up to 4 keys
+/// that are likely absent from it. Values range from 1 to 64 bytes, so they cross the threshold
+/// above which `LayoutV1` stores them hashed.
+pub fn arb_substrate_trie_proof<L: TrieLayout + 'static>(
+) -> impl Strategy<Value = TrieProof<TrieHash<L>>> {
+    (
+        btree_map(vec(any::<u8>(), 1..=32), vec(any::<u8>(), 1..=64), 1..=64),
+        vec(vec(any::<u8>(), 1..=32), 0..=4),
+    )
+        .prop_flat_map(|(entries, absent)| {
+            let present = entries.keys().cloned().collect::<Vec<_>>();
+            let len = present.len();
+            (Just(entries), subsequence(present, 1..=len), Just(absent))
+        })
+        .prop_map(|(entries, present, absent)| {
+            let entries = entries.into_iter().collect::<Vec<_>>();
+            let keys = present.into_iter().chain(absent).collect::<Vec<_>>();
+            generate_trie_proof::<L>(&entries, &keys)
+        })
+}
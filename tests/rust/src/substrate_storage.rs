@@ -0,0 +1,279 @@
+//! Keys of pallet storage items as FRAME lays them out, `twox128(pallet) ++ twox128(item)` followed
+//! by the hashed keys of maps, so that keys for `VerifySubstrateProof` needn't be assembled by
+//! hand.
+
+use codec::{Decode, DecodeAll, Encode};
+use sp_core::hashing::{blake2_128, blake2_256, twox_128, twox_64};
+
+/// Hashers for the keys of storage maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageHasher {
+    /// `blake2_128(key) ++ key`
+    Blake2_128Concat,
+    /// `twox64(key) ++ key`
+    Twox64Concat,
+    /// The key itself.
+    Identity,
+    /// `blake2_256(key)`, which cannot be reversed.
+    Blake2_256,
+}
+
+impl StorageHasher {
+    /// Hash a SCALE encoded key.
+    pub fn hash(&self, encoded: &[u8]) -> Vec<u8> {
+        match self {
+            StorageHasher::Blake2_128Concat => [&blake2_128(encoded)[..], encoded].concat(),
+            StorageHasher::Twox64Concat => [&twox_64(encoded)[..], encoded].concat(),
+            StorageHasher::Identity => encoded.to_vec(),
+            StorageHasher::Blake2_256 => blake2_256(encoded).to_vec(),
+        }
+    }
+
+    /// The length of the hash preceding the key for hashers that append the key, `None` for
+    /// hashers that don't.
+    fn concat_prefix_len(&self) -> Option<usize> {
+        match self {
+            StorageHasher::Blake2_128Concat => Some(16),
+            StorageHasher::Twox64Concat => Some(8),
+            StorageHasher::Identity => Some(0),
+            StorageHasher::Blake2_256 => None,
+        }
+    }
+}
+
+/// `twox128(pallet) ++ twox128(item)`, the key of a `StorageValue` and the prefix of the keys of
+/// the maps.
+pub fn storage_prefix(pallet: &str, item: &str) -> [u8; 32] {
+    let mut prefix = [0u8; 32];
+    prefix[..16].copy_from_slice(&twox_128(pallet.as_bytes()));
+    prefix[16..].copy_from_slice(&twox_128(item.as_bytes()));
+    prefix
+}
+
+/// Builds the key of a storage item: a `StorageValue` with no keys, a `StorageMap` with one, a
+/// `StorageDoubleMap` with two, or a `StorageNMap` with as many as it has.
+#[derive(Debug, Clone)]
+pub struct StorageKeyBuilder {
+    key: Vec<u8>,
+}
+
+impl StorageKeyBuilder {
+    pub fn new(pallet: &str, item: &str) -> Self {
+        Self { key: storage_prefix(pallet, item).to_vec() }
+    }
+
+    /// Append the next key of the map, hashed by `hasher`.
+    pub fn key<K: Encode>(mut self, hasher: StorageHasher, key: &K) -> Self {
+        self.key.extend(hasher.hash(&key.encode()));
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.key
+    }
+}
+
+/// Reads the keys of a map back from the key of one of its entries, for hashers that append the
+/// key to its hash.
+#[derive(Debug, Clone)]
+pub struct StorageKeyDecoder<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> StorageKeyDecoder<'a> {
+    /// Start decoding `key`, which must be a key of `pallet`'s `item`.
+    pub fn new(key: &'a [u8], pallet: &str, item: &str) -> Result<Self, String> {
+        let rest = key
+            .strip_prefix(&storage_prefix(pallet, item)[..])
+            .ok_or_else(|| format!("not a key of {pallet}::{item}"))?;
+        Ok(Self { rest })
+    }
+
+    /// Decode the next key of the map, hashed by `hasher`, checking that its hash matches.
+    pub fn key<K: Decode>(&mut self, hasher: StorageHasher) -> Result<K, String> {
+        let hash_len = hasher
+            .concat_prefix_len()
+            .ok_or_else(|| format!("keys hashed with {hasher:?} cannot be decoded"))?;
+        if self.rest.len() < hash_len {
+            return Err("storage key is too short".to_string())
+        }
+
+        let encoded = &mut &self.rest[hash_len..];
+        let key = K::decode(encoded).map_err(|e| format!("invalid key: {e}"))?;
+        let len = self.rest.len() - encoded.len();
+        if hasher.hash(&self.rest[hash_len..len]) != self.rest[..len] {
+            return Err(format!("key does not match its {hasher:?} hash"))
+        }
+        self.rest = &self.rest[len..];
+        Ok(key)
+    }
+
+    /// Require that every key was decoded.
+    pub fn finish(self) -> Result<(), String> {
+        if !self.rest.is_empty() {
+            return Err(format!("{} bytes left after the last key", self.rest.len()))
+        }
+        Ok(())
+    }
+}
+
+/// Decode a value proven by `VerifySubstrateProof`, which is SCALE encoded. Items absent from
+/// storage, with an empty value, are `None`.
+pub fn decode_storage_value<T: Decode>(value: &[u8]) -> Result<Option<T>, codec::Error> {
+    if value.is_empty() {
+        return Ok(None)
+    }
+    T::decode_all(&mut &value[..]).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkle_patricia::{proof_data, setup, VerifyKeysCall},
+        seeded_rng,
+        trie_utils::{generate_trie_proof, TrieProof},
+    };
+    use alloy_primitives::FixedBytes;
+    use alloy_sol_types::SolCall;
+    use hex_literal::hex;
+    use rand::Rng;
+    use sp_core::KeccakHasher;
+    use sp_trie::LayoutV0;
+
+    #[test]
+    fn test_substrate_storage_keys() {
+        let (_, _, timestamp_now) = proof_data();
+        assert_eq!(StorageKeyBuilder::new("Timestamp", "Now").build(), timestamp_now);
+        assert_eq!(
+            StorageKeyBuilder::new("System", "Number").build(),
+            hex!("26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac")
+        );
+
+        let alice = hex!("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d");
+        let key = StorageKeyBuilder::new("System", "Account")
+            .key(StorageHasher::Blake2_128Concat, &alice)
+            .build();
+        assert_eq!(key, hex!("26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9de1e86a9a8c739864cf3cc5ec2bea59fd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"));
+        let mut decoder = StorageKeyDecoder::new(&key, "System", "Account").unwrap();
+        assert_eq!(decoder.key::<[u8; 32]>(StorageHasher::Blake2_128Concat).unwrap(), alice);
+        decoder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_substrate_storage_key_decoding() {
+        let mut rng = seeded_rng();
+        let concat_hashers =
+            [StorageHasher::Blake2_128Concat, StorageHasher::Twox64Concat, StorageHasher::Identity];
+
+        for first in concat_hashers {
+            for second in concat_hashers {
+                let (id, who, data) =
+                    (rng.gen::<u32>(), rng.gen::<[u8; 32]>(), vec![rng.gen::<u8>(); 5]);
+                let key = StorageKeyBuilder::new("Pallet", "NMap")
+                    .key(first, &id)
+                    .key(second, &who)
+                    .key(StorageHasher::Twox64Concat, &data)
+                    .build();
+
+                let mut decoder = StorageKeyDecoder::new(&key, "Pallet", "NMap").unwrap();
+                assert_eq!(decoder.key::<u32>(first).unwrap(), id);
+                assert_eq!(decoder.key::<[u8; 32]>(second).unwrap(), who);
+                assert_eq!(decoder.key::<Vec<u8>>(StorageHasher::Twox64Concat).unwrap(), data);
+                decoder.finish().unwrap();
+            }
+        }
+
+        let key = StorageKeyBuilder::new("Pallet", "Map")
+            .key(StorageHasher::Blake2_128Concat, &7u64)
+            .build();
+        assert!(StorageKeyDecoder::new(&key, "Pallet", "Other").is_err());
+        // the key is followed by more than a u32
+        let mut decoder = StorageKeyDecoder::new(&key, "Pallet", "Map").unwrap();
+        decoder.key::<u32>(StorageHasher::Blake2_128Concat).unwrap_err();
+        let mut decoder = StorageKeyDecoder::new(&key, "Pallet", "Map").unwrap();
+        decoder.key::<u8>(StorageHasher::Identity).unwrap();
+        assert!(decoder.finish().is_err());
+
+        let mut tampered = key.clone();
+        tampered[32] ^= 1;
+        let mut decoder = StorageKeyDecoder::new(&tampered, "Pallet", "Map").unwrap();
+        assert!(decoder.key::<u64>(StorageHasher::Blake2_128Concat).is_err());
+
+        let key = StorageKeyBuilder::new("Pallet", "Map")
+            .key(StorageHasher::Blake2_256, &7u64)
+            .build();
+        let mut decoder = StorageKeyDecoder::new(&key, "Pallet", "Map").unwrap();
+        assert!(decoder.key::<u64>(StorageHasher::Blake2_256).is_err());
+    }
+
+    /// Keys built for each kind of storage item prove their SCALE encoded values with
+    /// `VerifySubstrateProof`, which decode back into the values stored.
+    #[test]
+    fn test_substrate_storage_items_proofs() {
+        let mut rng = seeded_rng();
+        let (who, asset) = (rng.gen::<[u8; 32]>(), rng.gen::<u32>());
+        let number = rng.gen::<u32>();
+        let account = (rng.gen::<u32>(), rng.gen::<u128>());
+        let balance = rng.gen::<u128>();
+        let metadata = (0..rng.gen_range(0..100)).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+        let hashed = rng.gen::<u64>();
+
+        let number_key = StorageKeyBuilder::new("System", "Number").build();
+        let account_key = StorageKeyBuilder::new("System", "Account")
+            .key(StorageHasher::Blake2_128Concat, &who)
+            .build();
+        let balance_key = StorageKeyBuilder::new("Assets", "Account")
+            .key(StorageHasher::Blake2_128Concat, &asset)
+            .key(StorageHasher::Blake2_128Concat, &who)
+            .build();
+        let metadata_key = StorageKeyBuilder::new("Pallet", "NMap")
+            .key(StorageHasher::Twox64Concat, &asset)
+            .key(StorageHasher::Identity, &who)
+            .key(StorageHasher::Blake2_128Concat, &7u16)
+            .build();
+        let hashed_key = StorageKeyBuilder::new("Pallet", "Hashed")
+            .key(StorageHasher::Blake2_256, &who)
+            .build();
+        let absent_key = StorageKeyBuilder::new("System", "Account")
+            .key(StorageHasher::Blake2_128Concat, &[0u8; 32])
+            .build();
+
+        let entries = vec![
+            (number_key.clone(), number.encode()),
+            (account_key.clone(), account.encode()),
+            (balance_key.clone(), balance.encode()),
+            (metadata_key.clone(), metadata.encode()),
+            (hashed_key.clone(), hashed.encode()),
+        ];
+        let keys =
+            [&number_key, &account_key, &balance_key, &metadata_key, &hashed_key, &absent_key]
+                .map(|key| key.to_vec());
+        let TrieProof { root, proof, .. } =
+            generate_trie_proof::<LayoutV0<KeccakHasher>>(&entries, &keys);
+
+        let (mut runner, addr) = setup();
+        let call = VerifyKeysCall {
+            root: FixedBytes(root.0),
+            proof: proof.into_iter().map(Into::into).collect(),
+            keys: keys.iter().cloned().map(Into::into).collect(),
+        };
+        let result = runner.call_raw(addr, call.abi_encode());
+        let values = VerifyKeysCall::abi_decode_returns(&result, true).unwrap()._0;
+        let values = values.iter().map(|value| value.value.to_vec()).collect::<Vec<_>>();
+
+        assert_eq!(decode_storage_value::<u32>(&values[0]).unwrap(), Some(number));
+        assert_eq!(decode_storage_value::<(u32, u128)>(&values[1]).unwrap(), Some(account));
+        assert_eq!(decode_storage_value::<u128>(&values[2]).unwrap(), Some(balance));
+        assert_eq!(decode_storage_value::<Vec<u8>>(&values[3]).unwrap(), Some(metadata));
+        assert_eq!(decode_storage_value::<u64>(&values[4]).unwrap(), Some(hashed));
+        assert_eq!(decode_storage_value::<(u32, u128)>(&values[5]).unwrap(), None);
+        // the whole value must be consumed
+        assert!(decode_storage_value::<u64>(&values[2]).is_err());
+
+        let mut decoder = StorageKeyDecoder::new(&balance_key, "Assets", "Account").unwrap();
+        assert_eq!(decoder.key::<u32>(StorageHasher::Blake2_128Concat).unwrap(), asset);
+        assert_eq!(decoder.key::<[u8; 32]>(StorageHasher::Blake2_128Concat).unwrap(), who);
+        decoder.finish().unwrap();
+    }
+}
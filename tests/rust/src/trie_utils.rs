@@ -0,0 +1,56 @@
+use sp_trie::{MemoryDB, StorageProof};
+use std::collections::HashSet;
+use trie_db::{Recorder, Trie, TrieDBBuilder, TrieDBMutBuilder, TrieHash, TrieLayout, TrieMut};
+
+/// A trie root, the proof nodes for a set of keys and the value of each key in the trie.
+#[derive(Debug, Clone)]
+pub struct TrieProof<H> {
+    pub root: H,
+    /// Proof nodes in the order they were first visited, i.e root first.
+    pub proof: Vec<Vec<u8>>,
+    /// The proven keys and their values, `None` for keys absent from the trie.
+    pub items: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+/// Build a trie from `entries` and record a proof for `keys`, which may include keys that are
+/// absent from the trie.
+pub fn generate_trie_proof<L: TrieLayout>(
+    entries: &[(Vec<u8>, Vec<u8>)],
+    keys: &[Vec<u8>],
+) -> TrieProof<TrieHash<L>> {
+    let mut db = <MemoryDB<L::Hash>>::default();
+    let mut root = Default::default();
+    {
+        let mut trie = TrieDBMutBuilder::<L>::new(&mut db, &mut root).build();
+        for (key, value) in entries {
+            trie.insert(key, value).unwrap();
+        }
+    }
+
+    let mut recorder = Recorder::<L>::new();
+    let items = {
+        let trie_db = TrieDBBuilder::<L>::new(&db, &root).with_recorder(&mut recorder).build();
+        keys.iter()
+            .map(|key| (key.clone(), trie_db.get(key).unwrap()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut seen = HashSet::new();
+    let proof = recorder
+        .drain()
+        .into_iter()
+        .map(|record| record.data)
+        .filter(|node| seen.insert(node.clone()))
+        .collect::<Vec<_>>();
+
+    // the recorded nodes alone must be enough to look up every key
+    {
+        let mdb = StorageProof::new(proof.clone()).into_memory_db::<L::Hash>();
+        let trie_db = TrieDBBuilder::<L>::new(&mdb, &root).build();
+        for (key, expected) in &items {
+            assert_eq!(&trie_db.get(key).unwrap(), expected);
+        }
+    }
+
+    TrieProof { root, proof, items }
+}